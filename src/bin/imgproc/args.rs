@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use vulkan_playground::{DeviceSelection, FilterStep, SampleType};
+
+pub const USAGE: &str = "usage: imgproc [options] INPUT...
+
+Runs each input image through a chain of filters on the GPU and writes the result.
+
+options:
+  --chain STEPS     filter steps separated by ';', e.g. \"linear; blur=2; srgb\"
+  --config FILE     file of filter steps, one per line, '#' starts a comment
+                    (--chain and --config may be repeated, the steps run in the order given)
+  --output PATH     output file, for a single input
+  --output-dir DIR  directory for the outputs, named like the inputs (default: next to each input, as NAME-out.EXT)
+  --format FMT      png, tiff, exr, ppm, or another format the image crate writes, like jpeg or bmp
+                    (default: from --output, otherwise the format of the input)
+  --depth D         8, 16, 16f or 32f bits per sample (default 32f for exr, otherwise 8).
+                    png and ppm take 8 or 16, exr 16f or 32f, tiff any, other formats only 8.
+  --device SEL      discrete, integrated, cpu, a device index or name (VK_PLAYGROUND_DEVICE takes precedence)
+  --help            print this message
+
+filter steps:
+  blur=SIGMA        Gaussian blur, SIGMA pixels wide (at most 100)
+  convolve=W,W,..   separable convolution, with the same odd-sized kernel along rows and columns
+  convolve-x=W,..   convolution along rows only
+  convolve-y=W,..   convolution along columns only
+  sobel             edge strength by the Sobel operator
+  gray              grayscale (Rec. 709 luma)
+  linear            decode sRGB to linear intensities
+  srgb              encode linear intensities as sRGB
+  resize=WxH        scale to W x H pixels, 0 for either keeps the aspect ratio
+
+Filters work on the pixel values as stored, which for most images is sRGB encoded:
+wrap them in linear and srgb for physically correct blurring and resizing.";
+
+pub struct Args {
+	pub inputs: Vec<PathBuf>,
+	pub steps: Vec<FilterStep>,
+	pub output: Option<PathBuf>,
+	pub output_dir: Option<PathBuf>,
+	/// File extension of the outputs, if not that of `output` or the inputs.
+	pub format: Option<String>,
+	pub depth: Option<SampleType>,
+	pub device: Option<DeviceSelection>,
+}
+
+impl Args {
+	/// Parse `std::env::args`. `Err` holds a message for the user, or the usage text for `--help`.
+	pub fn from_env() -> Result<Self, String> {
+		Self::parse(std::env::args().skip(1))
+	}
+
+	pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+		let mut inputs = Vec::new();
+		let mut steps = Vec::new();
+		let mut output = None;
+		let mut output_dir = None;
+		let mut format = None;
+		let mut depth = None;
+		let mut device = None;
+
+		while let Some(arg) = args.next() {
+			if arg == "--help" || arg == "-h" {
+				return Err(USAGE.to_string());
+			}
+			if !arg.starts_with('-') {
+				inputs.push(PathBuf::from(arg));
+				continue;
+			}
+			// both `--flag value` and `--flag=value`
+			let (flag, inline) = match arg.find('=') {
+				Some(i) => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+				None => (arg.clone(), None),
+			};
+			// taken by the options that know the flag, so that an unknown one is reported as such
+			let mut inline = inline;
+			let mut value = || inline.take().or_else(|| args.next()).ok_or_else(|| format!("{}: missing value", flag));
+			match flag.as_str() {
+				"--chain" => steps.extend(FilterStep::parse_chain(&value()?).map_err(|e| format!("--chain: {}", e))?),
+				"--config" => {
+					let path = value()?;
+					let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+					steps.extend(FilterStep::parse_chain(&text).map_err(|e| format!("{}: {}", path, e))?);
+				}
+				"--output" => output = Some(PathBuf::from(value()?)),
+				"--output-dir" => output_dir = Some(PathBuf::from(value()?)),
+				"--format" => format = Some(value()?.to_lowercase()),
+				"--depth" => {
+					let name = value()?;
+					depth = Some(parse_depth(&name).ok_or_else(|| format!("--depth: unknown depth {:?}", name))?);
+				}
+				"--device" => device = Some(value()?.parse().unwrap()),
+				_ => return Err(format!("unknown option {:?}\n\n{}", flag, USAGE)),
+			}
+		}
+
+		if inputs.is_empty() {
+			return Err(format!("no input images\n\n{}", USAGE));
+		}
+		if output.is_some() && inputs.len() > 1 {
+			return Err("--output: only applies to a single input, use --output-dir".to_string());
+		}
+		if output.is_some() && output_dir.is_some() {
+			return Err("--output and --output-dir: use only one".to_string());
+		}
+
+		let args = Self {
+			inputs,
+			steps,
+			output,
+			output_dir,
+			format,
+			depth,
+			device,
+		};
+		// inputs of the same name in different directories, or the same input twice, would overwrite each other's output
+		let mut outputs = HashMap::new();
+		for input in &args.inputs {
+			if let Some(first) = outputs.insert(args.output_name(input), input) {
+				return Err(format!(
+					"{} and {}: both would be written to {}, choose another --format or process them separately",
+					first.display(),
+					input.display(),
+					args.output_name(input).display()
+				));
+			}
+		}
+		Ok(args)
+	}
+
+	/// Where to write the result for `input`. `Err` if that is one of the inputs, which would be overwritten.
+	pub fn output_path(&self, input: &Path) -> Result<PathBuf, String> {
+		let path = self.output_name(input);
+		// an output that does not exist yet cannot be an input; otherwise compare the resolved paths
+		if let Ok(resolved) = path.canonicalize() {
+			if let Some(overwritten) = self.inputs.iter().find(|i| i.canonicalize().ok().as_ref() == Some(&resolved)) {
+				return Err(format!(
+					"{}: the output {} would overwrite this input, choose another --output-dir or a --format",
+					overwritten.display(),
+					path.display()
+				));
+			}
+		}
+		Ok(path)
+	}
+
+	fn output_name(&self, input: &Path) -> PathBuf {
+		match &self.output {
+			Some(output) => output.clone(),
+			None => {
+				let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+				let extension = match &self.format {
+					Some(format) => format.clone(),
+					None => input.extension().unwrap_or_default().to_string_lossy().into_owned(),
+				};
+				match &self.output_dir {
+					Some(dir) => dir.join(format!("{}.{}", stem, extension)),
+					None => input.with_file_name(format!("{}-out.{}", stem, extension)),
+				}
+			}
+		}
+	}
+}
+
+fn parse_depth(name: &str) -> Option<SampleType> {
+	match name {
+		"8" => Some(SampleType::U8),
+		"16" => Some(SampleType::U16),
+		"16f" => Some(SampleType::F16),
+		"32f" => Some(SampleType::F32),
+		_ => None,
+	}
+}
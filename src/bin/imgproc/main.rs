@@ -0,0 +1,119 @@
+use half::f16;
+use std::path::Path;
+use vulkan_playground::*;
+
+mod args;
+use args::Args;
+
+fn main() {
+	let args = Args::from_env().unwrap_or_else(|msg| {
+		eprintln!("{}", msg);
+		std::process::exit(2)
+	});
+	let started = now();
+
+	let mut builder = Interface::builder();
+	if let Some(device) = args.device.clone() {
+		builder = builder.device(device);
+	}
+	let vk = builder.build().unwrap_or_else(|e| panic!("{}", e));
+	println!("using {}", vk.info());
+	let chain = FilterChain::new(&vk, &args.steps);
+	println!("init: {} ms", started.elapsed().as_secs_f32() * 1000.0);
+
+	// Keep going after a bad input, so that one file does not stop a whole batch.
+	let mut failed = 0;
+	for input in &args.inputs {
+		let output = match args.output_path(input) {
+			Ok(output) => output,
+			Err(msg) => {
+				eprintln!("{}", msg);
+				failed += 1;
+				continue;
+			}
+		};
+		let started = now();
+		match process(&vk, &chain, &args, input, &output) {
+			Ok([w, h]) => println!(
+				"{} -> {} ({}x{}): {} ms",
+				input.display(),
+				output.display(),
+				w,
+				h,
+				started.elapsed().as_secs_f32() * 1000.0
+			),
+			Err(msg) => {
+				eprintln!("{}", msg);
+				failed += 1;
+			}
+		}
+	}
+	if failed > 0 {
+		fatal(format!("{} of {} images failed", failed, args.inputs.len()));
+	}
+}
+
+// Filter the image at `input` into `output`, returning the output size.
+fn process(vk: &Interface, chain: &FilterChain, args: &Args, input: &Path, output: &Path) -> Result<[u32; 2], String> {
+	let image = image::open(input).map_err(|e| format!("{}: {}", input.display(), e))?;
+	let (w, h) = image::GenericImageView::dimensions(&image);
+	let [ow, oh] = chain.output_size([w, h]);
+	if ow == 0 || oh == 0 {
+		return Err(format!("{}: empty output", input.display()));
+	}
+	let filtered = chain.apply(vk, &image);
+	let data = vk.read_image_data(&filtered);
+	let samples: Vec<f32> = data.chunks_exact(4).map(|s| f32::from_ne_bytes([s[0], s[1], s[2], s[3]])).collect();
+	write(output, args, [ow, oh], &samples)?;
+	Ok([ow, oh])
+}
+
+// Write RGBA float pixels in the output format and depth.
+fn write(path: &Path, args: &Args, [w, h]: [u32; 2], samples: &[f32]) -> Result<(), String> {
+	let err = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+	let extension = match &args.format {
+		Some(format) => format.clone(),
+		None => path.extension().unwrap_or_default().to_string_lossy().to_lowercase(),
+	};
+
+	if let Some(format) = FileFormat::from_extension(&extension) {
+		let sample = args.depth.unwrap_or(match format {
+			FileFormat::Exr => SampleType::F32,
+			_ => SampleType::U8,
+		});
+		let layout = ExportLayout::new(w, h, 4, sample);
+		if !format.supports(layout) {
+			return Err(err(&format!("{:?} does not take {:?} samples", format, sample)));
+		}
+		let mut export = Export::with_format(path, format, layout).map_err(|e| err(&e))?;
+		export.write_rows(&encode(samples, sample)).map_err(|e| err(&e))?;
+		return export.finish().map_err(|e| err(&e));
+	}
+
+	let format = image::ImageFormat::from_path(Path::new("image").with_extension(&extension)).map_err(|e| err(&e))?;
+	if args.depth.unwrap_or(SampleType::U8) != SampleType::U8 {
+		return Err(err(&format!("{:?} only takes 8 bit samples", format)));
+	}
+	let image = image::RgbaImage::from_raw(w, h, encode(samples, SampleType::U8)).unwrap();
+	image.save_with_format(path, format).map_err(|e| err(&e))
+}
+
+// Samples in native byte order, unsigned ones clamped to [0, 1].
+fn encode(samples: &[f32], sample: SampleType) -> Vec<u8> {
+	let unorm = |v: f32, max: f32| (v.clamp(0.0, 1.0) * max).round();
+	match sample {
+		SampleType::U8 => samples.iter().map(|&v| unorm(v, 255.0) as u8).collect(),
+		SampleType::U16 => samples.iter().flat_map(|&v| (unorm(v, 65535.0) as u16).to_ne_bytes()).collect(),
+		SampleType::F16 => samples.iter().flat_map(|&v| f16::from_f32(v).to_bits().to_ne_bytes()).collect(),
+		SampleType::F32 => samples.iter().flat_map(|&v| v.to_ne_bytes()).collect(),
+	}
+}
+
+fn fatal(msg: impl std::fmt::Display) -> ! {
+	eprintln!("{}", msg);
+	std::process::exit(1)
+}
+
+fn now() -> std::time::Instant {
+	std::time::Instant::now()
+}
@@ -0,0 +1,302 @@
+use crate::fractal::Fractal;
+use crate::output::OutputFormat;
+use std::path::PathBuf;
+use vulkan_playground::{DeviceSelection, DownsampleFilter, FileFormat, Format};
+
+pub const USAGE: &str = "usage: mandelbrot [options]
+
+options:
+  --width N         image width in pixels (default 2048)
+  --height N        image height in pixels (default 2048)
+  --fractal F       mandelbrot, julia, burning-ship, tricorn or multibrot (default mandelbrot)
+  --c X,Y           constant of the julia set (default -0.8,0.156)
+  --power N         exponent of the multibrot set, at least 2 (default 3)
+  --center X,Y      complex coordinate of the image center, any number of digits (default: whole fractal in view)
+  --zoom Z          magnification, 1 shows 3 units vertically (default 1)
+  --iterations N    maximum iteration count (default 64)
+  --supersample N   render N x N samples per pixel (default 1)
+  --filter F        box or lanczos, to combine the samples of a pixel (default box)
+  --tile N          render in tiles of at most N x N pixels, to bound memory use (default 2048).
+                    Memory still grows with the width: the encoder takes a band of N rows of the whole image.
+  --precision P     f32, f64, perturbation or auto (default auto: the cheapest one that resolves the pixels)
+  --palette NAME    grey, ultra, fire, ocean or a palette file of \"position #rrggbb\" lines (default ultra)
+  --equalize        spread the palette by histogram equalization, instead of linearly over the iterations
+  --output PATH     output file (default image.png)
+  --format FMT      png, tiff, exr, ppm, jpeg, bmp, tga or farbfeld (default: from output extension).
+                    jpeg, bmp, tga and farbfeld only for images of a single tile.
+  --pixels P        rgba8, rgba16f or rgba32f colors, or iterations: the raw smooth iteration counts as 32 bit floats
+                    (default rgba16f for exr, otherwise rgba8). Float colors are linear, rgba8 is sRGB encoded.
+  --device SEL      discrete, integrated, cpu, a device index or name (VK_PLAYGROUND_DEVICE takes precedence)
+  --help            print this message";
+
+/// Arithmetic used to iterate the pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Precision {
+	/// The cheapest of the others that still resolves the pixels.
+	Auto,
+	F32,
+	/// Needs `shaderFloat64`.
+	F64,
+	/// Deltas from a reference orbit computed on the CPU, see perturbation.rs.
+	Perturbation,
+}
+
+impl Precision {
+	/// Resolve `Auto` for the given pixel size.
+	/// Without perturbation, beyond f64 precision, the pixels are not resolved but still rendered.
+	pub fn resolve(self, pixel_size: f64, f64_supported: bool, fractal: Fractal) -> Self {
+		if self != Precision::Auto {
+			self
+		} else if pixel_size > 1e-5 {
+			Precision::F32
+		} else if (pixel_size > 1e-13 || !fractal.supports_perturbation()) && f64_supported {
+			Precision::F64
+		} else if fractal.supports_perturbation() {
+			Precision::Perturbation
+		} else {
+			Precision::F32
+		}
+	}
+}
+
+pub struct Args {
+	pub width: u32,
+	pub height: u32,
+	pub fractal: Fractal,
+	/// Decimal coordinates, kept as text for perturbation rendering beyond f64 precision.
+	pub center: [String; 2],
+	pub zoom: f64,
+	pub iterations: u32,
+	/// Samples per pixel in each direction.
+	pub supersample: u32,
+	pub filter: DownsampleFilter,
+	/// Maximum tile width and height, in pixels.
+	pub tile: u32,
+	pub precision: Precision,
+	/// Built-in palette name or palette file.
+	pub palette: String,
+	pub equalize: bool,
+	pub output: PathBuf,
+	pub format: OutputFormat,
+	/// Pixel format of the output: a color format, or `R32Sfloat` for the iteration counts.
+	pub pixels: Format,
+	pub device: Option<DeviceSelection>,
+}
+
+impl Args {
+	/// Parse `std::env::args`. `Err` holds a message for the user, or the usage text for `--help`.
+	pub fn from_env() -> Result<Self, String> {
+		Self::parse(std::env::args().skip(1))
+	}
+
+	pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+		let mut width: u32 = 2048;
+		let mut height: u32 = 2048;
+		let mut fractal = "mandelbrot".to_string();
+		let mut julia_c = None;
+		let mut power = None;
+		let mut center = None;
+		let mut zoom: f64 = 1.0;
+		let mut iterations = 64;
+		let mut supersample = 1;
+		let mut filter = DownsampleFilter::Box;
+		let mut tile = 2048;
+		let mut precision = Precision::Auto;
+		let mut palette = "ultra".to_string();
+		let mut equalize = false;
+		let mut output = PathBuf::from("image.png");
+		let mut format = None;
+		let mut pixels = None;
+		let mut device = None;
+
+		while let Some(arg) = args.next() {
+			if arg == "--help" || arg == "-h" {
+				return Err(USAGE.to_string());
+			}
+			if arg == "--equalize" {
+				equalize = true;
+				continue;
+			}
+			// both `--flag value` and `--flag=value`
+			let (flag, inline) = match arg.find('=') {
+				Some(i) => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+				None => (arg.clone(), None),
+			};
+			// taken by the options that know the flag, so that an unknown one is reported as such
+			let mut inline = inline;
+			let mut value = || inline.take().or_else(|| args.next()).ok_or_else(|| format!("{}: missing value", flag));
+			match flag.as_str() {
+				"--width" => width = parse_num(&flag, &value()?)?,
+				"--height" => height = parse_num(&flag, &value()?)?,
+				"--fractal" => fractal = value()?,
+				"--c" => julia_c = Some(parse_point(&flag, &value()?)?),
+				"--power" => power = Some(parse_num(&flag, &value()?)?),
+				"--center" => center = Some(parse_point(&flag, &value()?)?),
+				"--zoom" => zoom = parse_num(&flag, &value()?)?,
+				"--iterations" => iterations = parse_num(&flag, &value()?)?,
+				"--supersample" => supersample = parse_num(&flag, &value()?)?,
+				"--tile" => tile = parse_num(&flag, &value()?)?,
+				"--filter" => {
+					let name = value()?;
+					filter = parse_filter(&name).ok_or_else(|| format!("--filter: unknown filter {:?}", name))?;
+				}
+				"--precision" => {
+					let name = value()?;
+					precision = parse_precision(&name).ok_or_else(|| format!("--precision: unknown precision {:?}", name))?;
+				}
+				"--palette" => palette = value()?,
+				"--output" => output = PathBuf::from(value()?),
+				"--format" => {
+					let name = value()?;
+					format = Some(OutputFormat::parse(&name).ok_or_else(|| format!("--format: unsupported format {:?}", name))?);
+				}
+				"--pixels" => {
+					let name = value()?;
+					pixels = Some(parse_pixels(&name).ok_or_else(|| format!("--pixels: unknown pixel format {:?}", name))?);
+				}
+				"--device" => device = Some(value()?.parse().unwrap()),
+				_ => return Err(format!("unknown option {:?}\n\n{}", flag, USAGE)),
+			}
+		}
+
+		let fractal = match (fractal.as_str(), julia_c, power) {
+			("julia", c, None) => {
+				let c = c.unwrap_or_else(|| ["-0.8".to_string(), "0.156".to_string()]);
+				Fractal::Julia {
+					c: [c[0].parse().unwrap(), c[1].parse().unwrap()],
+				}
+			}
+			("multibrot", None, power) => match power.unwrap_or(3) {
+				power if power >= 2 => Fractal::Multibrot { power },
+				power => return Err(format!("--power: must be at least 2, got {}", power)),
+			},
+			(name, None, None) => parse_fractal(name).ok_or_else(|| format!("--fractal: unknown fractal {:?}", name))?,
+			(name, _, Some(_)) if name != "multibrot" => return Err(format!("--power: only applies to --fractal multibrot, not {}", name)),
+			(name, _, _) => return Err(format!("--c: only applies to --fractal julia, not {}", name)),
+		};
+		let center = center.unwrap_or_else(|| fractal.default_center().map(String::from));
+
+		if width == 0 || height == 0 {
+			return Err(format!("invalid size {}x{}", width, height));
+		}
+		if zoom.is_nan() || zoom <= 0.0 {
+			return Err(format!("--zoom: must be positive, got {}", zoom));
+		}
+		if iterations == 0 {
+			return Err("--iterations: must be at least 1".to_string());
+		}
+		if supersample == 0 {
+			return Err("--supersample: must be at least 1".to_string());
+		}
+		if width.checked_mul(supersample).is_none() || height.checked_mul(supersample).is_none() {
+			return Err(format!(
+				"--supersample: {} x {} samples per pixel of a {}x{} image are too many",
+				supersample, supersample, width, height
+			));
+		}
+		if tile == 0 {
+			return Err("--tile: must be at least 1".to_string());
+		}
+		let format = match format {
+			Some(f) => f,
+			None => OutputFormat::from_path(&output).ok_or_else(|| format!("{}: cannot infer format, use --format", output.display()))?,
+		};
+		let pixels = match (pixels, format) {
+			(Some(pixels), _) => pixels,
+			(None, OutputFormat::Export(FileFormat::Exr)) => Format::R16G16B16A16Sfloat,
+			(None, _) => Format::R8G8B8A8Unorm,
+		};
+		if pixels == Format::R32Sfloat && equalize {
+			return Err("--equalize: does not apply to --pixels iterations".to_string());
+		}
+
+		Ok(Self {
+			width,
+			height,
+			fractal,
+			center,
+			zoom,
+			iterations,
+			supersample,
+			filter,
+			tile,
+			precision,
+			palette,
+			equalize,
+			output,
+			format,
+			pixels,
+			device,
+		})
+	}
+
+	pub fn center_f64(&self) -> [f64; 2] {
+		[self.center[0].parse().unwrap(), self.center[1].parse().unwrap()]
+	}
+
+	/// Size of the rendered image, before downsampling to `width` x `height`.
+	/// Checked by `parse` to fit in `u32`.
+	pub fn render_size(&self) -> (u32, u32) {
+		(self.width * self.supersample, self.height * self.supersample)
+	}
+
+	/// Distance between neighbouring pixels of the rendered image in the complex plane.
+	pub fn pixel_size(&self) -> f64 {
+		3.0 / (self.zoom * self.render_size().1 as f64)
+	}
+}
+
+fn parse_num<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+	value.trim().parse().map_err(|_| format!("{}: invalid number {:?}", flag, value))
+}
+
+// X,Y as decimal strings, checked to be valid numbers.
+fn parse_point(flag: &str, value: &str) -> Result<[String; 2], String> {
+	let mut parts = value.split(',');
+	match (parts.next(), parts.next(), parts.next()) {
+		(Some(x), Some(y), None) => {
+			parse_num::<f64>(flag, x)?;
+			parse_num::<f64>(flag, y)?;
+			Ok([x.trim().to_string(), y.trim().to_string()])
+		}
+		_ => Err(format!("{}: expected X,Y, got {:?}", flag, value)),
+	}
+}
+
+// Fractals without parameters.
+fn parse_fractal(name: &str) -> Option<Fractal> {
+	match name {
+		"mandelbrot" => Some(Fractal::Mandelbrot),
+		"burning-ship" => Some(Fractal::BurningShip),
+		"tricorn" => Some(Fractal::Tricorn),
+		_ => None,
+	}
+}
+
+fn parse_filter(name: &str) -> Option<DownsampleFilter> {
+	match name {
+		"box" => Some(DownsampleFilter::Box),
+		"lanczos" | "lanczos3" => Some(DownsampleFilter::Lanczos3),
+		_ => None,
+	}
+}
+
+fn parse_pixels(name: &str) -> Option<Format> {
+	match name {
+		"rgba8" => Some(Format::R8G8B8A8Unorm),
+		"rgba16f" => Some(Format::R16G16B16A16Sfloat),
+		"rgba32f" => Some(Format::R32G32B32A32Sfloat),
+		"iterations" => Some(Format::R32Sfloat),
+		_ => None,
+	}
+}
+
+fn parse_precision(name: &str) -> Option<Precision> {
+	match name {
+		"auto" => Some(Precision::Auto),
+		"f32" => Some(Precision::F32),
+		"f64" => Some(Precision::F64),
+		"perturbation" => Some(Precision::Perturbation),
+		_ => None,
+	}
+}
@@ -0,0 +1,178 @@
+//! Coloring of smooth iteration counts, optionally histogram-equalized, all on the GPU.
+
+use crate::palette::Palette;
+use vulkano::buffer::TypedBufferAccess;
+use vulkano::image::ImageAccess;
+use vulkano::sync::GpuFuture;
+
+use vulkan_playground::*;
+
+mod colorize_cs {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/colorize.glsl",
+	}
+}
+
+mod colorize_rgba16f_cs {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/colorize.glsl",
+		define: [("FORMAT", "rgba16f"), ("LINEAR", "1")],
+	}
+}
+
+mod colorize_rgba32f_cs {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/colorize.glsl",
+		define: [("FORMAT", "rgba32f"), ("LINEAR", "1")],
+	}
+}
+
+/// Number of palette colors uploaded; the shader interpolates between them.
+const PALETTE_SIZE: usize = 256;
+
+/// Color of points that did not escape.
+pub const INSIDE: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// Coloring of smooth iteration counts.
+///
+/// Histogram equalization needs the histogram of the whole image before coloring any part of it.
+/// For an image rendered in tiles, add each tile to one histogram, make it cumulative,
+/// and then color the tiles with it:
+///
+/// ```ignore
+/// let histogram = colorizer.histogram(vk, &mut builder, iterations);
+/// for tile in tiles { colorizer.record_histogram(vk, &mut builder, tile_values, histogram.clone()) }
+/// let cdf = colorizer.record_cumulative(vk, &mut builder, histogram);
+/// for tile in tiles { colorizer.record_colorize(&mut builder, tile_values, tile_output, iterations, Some(cdf.clone())) }
+/// ```
+pub struct Colorizer {
+	histogram: Histogram<f32>,
+	cumulative: Scan<u32>,
+	colorize: Kernel<colorize_cs::ty::PushConstants>,
+	colorize_rgba16f: Kernel<colorize_rgba16f_cs::ty::PushConstants>,
+	colorize_rgba32f: Kernel<colorize_rgba32f_cs::ty::PushConstants>,
+	palette: Arc<DeviceLocalBuffer<[[f32; 4]]>>,
+	// bound in place of the cumulative histogram when not equalizing
+	no_cdf: Arc<DeviceLocalBuffer<[u32]>>,
+}
+
+impl Colorizer {
+	/// Load the kernels and upload `palette`.
+	pub fn new(vk: &Interface, palette: &Palette) -> Self {
+		let colorize = colorize_cs::Shader::load(vk.device()).unwrap();
+		let colorize_rgba16f = colorize_rgba16f_cs::Shader::load(vk.device()).unwrap();
+		let colorize_rgba32f = colorize_rgba32f_cs::Shader::load(vk.device()).unwrap();
+
+		let (palette, uploaded) = vk.upload(&palette.lut(PALETTE_SIZE), storage_buffer());
+		uploaded.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+
+		Self {
+			histogram: Histogram::new(vk),
+			cumulative: Scan::new(vk),
+			colorize: Kernel::new(vk, &colorize.main_entry_point()),
+			colorize_rgba16f: Kernel::new(vk, &colorize_rgba16f.main_entry_point()),
+			colorize_rgba32f: Kernel::new(vk, &colorize_rgba32f.main_entry_point()),
+			palette,
+			no_cdf: vk.device_local_buffer(1, storage_buffer()),
+		}
+	}
+
+	/// New histogram of escape iterations, one bin per iteration, zeroed by a command recorded in `builder`.
+	pub fn histogram(&self, vk: &Interface, builder: &mut AutoCommandBufferBuilder, iterations: u32) -> Arc<DeviceLocalBuffer<[u32]>> {
+		self.histogram.counts(vk, builder, iterations as usize)
+	}
+
+	/// Record the commands adding the escape iterations in `values` (R32Sfloat) to `histogram`.
+	/// Points that did not escape are not counted.
+	pub fn record_histogram(
+		&self,
+		vk: &Interface,
+		builder: &mut AutoCommandBufferBuilder,
+		values: Arc<StorageImage<Format>>,
+		histogram: Arc<DeviceLocalBuffer<[u32]>>,
+	) {
+		let [w, h] = values.dimensions().width_height();
+		let usage = BufferUsage {
+			transfer_destination: true,
+			..storage_buffer()
+		};
+		let samples = vk.device_local_buffer::<f32>(w as usize * h as usize, usage);
+		builder.copy_image_to_buffer(values, samples.clone()).unwrap();
+		// one bin per iteration; points that did not escape hold the iteration count, past the last bin
+		let iterations = histogram.len() as f32;
+		self.histogram.record(builder, samples, 0.0..iterations, histogram);
+	}
+
+	/// Record the dispatches that sum up `histogram` into a new cumulative histogram, and return it.
+	pub fn record_cumulative(
+		&self,
+		vk: &Interface,
+		builder: &mut AutoCommandBufferBuilder,
+		histogram: Arc<DeviceLocalBuffer<[u32]>>,
+	) -> Arc<DeviceLocalBuffer<[u32]>> {
+		self.cumulative.record(vk, builder, histogram, ScanKind::Inclusive)
+	}
+
+	/// Record a dispatch that colors the smooth iteration counts in `values` (R32Sfloat)
+	/// into `output` of the same size.
+	///
+	/// `output` is `R8G8B8A8Unorm`, receiving sRGB encoded colors,
+	/// or `R16G16B16A16Sfloat` or `R32G32B32A32Sfloat`, receiving linear intensities. Panics for other formats.
+	///
+	/// With a cumulative histogram `cdf`, colors are equalized: each palette color covers about the same number of pixels.
+	/// Otherwise they are spread linearly over the iterations.
+	pub fn record_colorize(
+		&self,
+		builder: &mut AutoCommandBufferBuilder,
+		values: Arc<StorageImage<Format>>,
+		output: Arc<StorageImage<Format>>,
+		iterations: u32,
+		cdf: Option<Arc<DeviceLocalBuffer<[u32]>>>,
+	) {
+		let [w, h] = output.dimensions().width_height();
+		let equalize = cdf.is_some() as u32;
+		let cdf = cdf.unwrap_or_else(|| self.no_cdf.clone());
+		let size = [w, h, 1];
+		// the push constants of all variants have the same layout, but distinct types
+		match output.format() {
+			Format::R8G8B8A8Unorm => {
+				let params = colorize_cs::ty::PushConstants {
+					inside: INSIDE,
+					iterations,
+					equalize,
+				};
+				self.colorize
+					.record_dispatch(builder, size, (values, output, self.palette.clone(), cdf), params)
+			}
+			Format::R16G16B16A16Sfloat => {
+				let params = colorize_rgba16f_cs::ty::PushConstants {
+					inside: INSIDE,
+					iterations,
+					equalize,
+				};
+				self.colorize_rgba16f
+					.record_dispatch(builder, size, (values, output, self.palette.clone(), cdf), params)
+			}
+			Format::R32G32B32A32Sfloat => {
+				let params = colorize_rgba32f_cs::ty::PushConstants {
+					inside: INSIDE,
+					iterations,
+					equalize,
+				};
+				self.colorize_rgba32f
+					.record_dispatch(builder, size, (values, output, self.palette.clone(), cdf), params)
+			}
+			format => panic!("colorize: unsupported output format {:?}", format),
+		}
+	}
+}
+
+fn storage_buffer() -> BufferUsage {
+	BufferUsage {
+		storage_buffer: true,
+		..BufferUsage::none()
+	}
+}
@@ -0,0 +1,77 @@
+#version 450
+
+// Maps smooth iteration counts to palette colors.
+//
+// Compiled with FORMAT defined as the output's format qualifier (default rgba8).
+// The palette is sRGB encoded; with LINEAR defined, as for float outputs, the colors are stored as linear intensities.
+#ifndef FORMAT
+#define FORMAT rgba8
+#endif
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+// smooth iteration counts
+layout(set = 0, binding = 0, r32f) uniform readonly image2D values;
+
+layout(set = 0, binding = 1, FORMAT) uniform writeonly image2D img;
+
+// palette colors, evenly spaced from 0 to 1
+layout(set = 0, binding = 2) readonly buffer Palette {
+    vec4 colors[];
+};
+
+// cumulative histogram, one bin per iteration (only read when equalizing)
+layout(set = 0, binding = 3) readonly buffer Cumulative {
+    uint cdf[];
+};
+
+layout(push_constant) uniform PushConstants {
+    // color of points that did not escape
+    vec4 inside;
+    uint iterations;
+    // bool: map through the cumulative histogram, so that all colors get equal area
+    uint equalize;
+} params;
+
+vec4 palette(float t) {
+    float x = clamp(t, 0.0, 1.0) * float(colors.length() - 1);
+    uint i = min(uint(x), uint(colors.length()) - 1);
+    uint j = min(i + 1, uint(colors.length()) - 1);
+    return mix(colors[i], colors[j], x - float(i));
+}
+
+vec4 encode(vec4 color) {
+#ifdef LINEAR
+    vec3 c = color.rgb;
+    vec3 linear = mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), greaterThan(c, vec3(0.04045)));
+    return vec4(linear, color.a);
+#else
+    return color;
+#endif
+}
+
+void main() {
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(img))))) {
+        return;
+    }
+
+    ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    float v = imageLoad(values, pos).r;
+    if (v >= float(params.iterations)) {
+        imageStore(img, pos, encode(params.inside));
+        return;
+    }
+
+    float t;
+    if (params.equalize != 0) {
+        // Interpolate within the bin, so that equalization keeps the smooth gradient.
+        uint bin = min(uint(v), params.iterations - 1);
+        float below = bin == 0 ? 0.0 : float(cdf[bin - 1]);
+        float total = float(cdf[params.iterations - 1]);
+        t = mix(below, float(cdf[bin]), fract(v)) / total;
+    } else {
+        t = v / float(params.iterations);
+    }
+    imageStore(img, pos, encode(vec4(palette(t).rgb, 1.0)));
+}
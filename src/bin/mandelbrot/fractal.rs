@@ -0,0 +1,65 @@
+//! Fractal families rendered by mandelbrot.glsl.
+
+/// Escape-time fractal, selected in mandelbrot.glsl through specialization constants.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Fractal {
+	/// z² + c, with z starting at 0 and c the pixel.
+	Mandelbrot,
+	/// z² + c, with z starting at the pixel and a fixed c.
+	Julia { c: [f64; 2] },
+	/// (|Re z| + i |Im z|)² + c
+	BurningShip,
+	/// conj(z)² + c
+	Tricorn,
+	/// z^power + c, for power ≥ 2.
+	Multibrot { power: u32 },
+}
+
+// Values of the `formula` specialization constant, as defined in mandelbrot.glsl.
+const QUADRATIC: u32 = 0;
+const BURNING_SHIP: u32 = 1;
+const TRICORN: u32 = 2;
+const MULTIBROT: u32 = 3;
+
+impl Fractal {
+	/// Value of the `formula` specialization constant.
+	pub fn formula(&self) -> u32 {
+		match self {
+			Fractal::Mandelbrot | Fractal::Julia { .. } => QUADRATIC,
+			Fractal::BurningShip => BURNING_SHIP,
+			Fractal::Tricorn => TRICORN,
+			Fractal::Multibrot { .. } => MULTIBROT,
+		}
+	}
+
+	/// Value of the `power` specialization constant.
+	pub fn power(&self) -> u32 {
+		match self {
+			Fractal::Multibrot { power } => *power,
+			_ => 2,
+		}
+	}
+
+	/// c of a Julia set, which is also the value of the `julia` specialization constant.
+	pub fn julia_c(&self) -> Option<[f64; 2]> {
+		match self {
+			Fractal::Julia { c } => Some(*c),
+			_ => None,
+		}
+	}
+
+	/// Center of a view that shows the whole fractal.
+	pub fn default_center(&self) -> [&'static str; 2] {
+		match self {
+			Fractal::Mandelbrot => ["-0.75", "0"],
+			Fractal::BurningShip => ["-0.5", "-0.5"],
+			Fractal::Tricorn => ["-0.25", "0"],
+			Fractal::Julia { .. } | Fractal::Multibrot { .. } => ["0", "0"],
+		}
+	}
+
+	/// Whether `Precision::Perturbation` can render this fractal. Only the Mandelbrot set has a perturbation kernel.
+	pub fn supports_perturbation(&self) -> bool {
+		*self == Fractal::Mandelbrot
+	}
+}
@@ -10,78 +10,226 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
-use image::ImageBuffer;
-use image::Rgba;
+use std::collections::VecDeque;
 use vulkano::command_buffer::CommandBuffer;
-use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
-use vulkano::descriptor::pipeline_layout::PipelineLayoutAbstract;
 use vulkano::format::Format;
-use vulkano::pipeline::ComputePipeline;
-use vulkano::sync::GpuFuture;
+use vulkano::sync::{FenceSignalFuture, GpuFuture};
 
 use vulkan_playground::*;
 
+mod args;
+mod color;
+mod fractal;
+mod output;
+mod palette;
+mod perturbation;
+mod render;
+mod tiles;
+use args::Args;
+use output::{Output, OutputFormat};
+use palette::Palette;
+use tiles::Tile;
+
 fn main() {
+	let args = Args::from_env().unwrap_or_else(|msg| {
+		eprintln!("{}", msg);
+		std::process::exit(2)
+	});
+	let palette = Palette::load(&args.palette).unwrap_or_else(|msg| {
+		eprintln!("{}", msg);
+		std::process::exit(2)
+	});
 	let started = now();
 
 	// init
-	let vk = Interface::new_compute();
+	let mut builder = Interface::builder().optional_features(render::optional_features());
+	if let Some(device) = args.device.clone() {
+		builder = builder.device(device);
+	}
+	let vk = builder.build().unwrap_or_else(|e| panic!("{}", e));
 	println!("using {}", vk.info());
+	let precision = args
+		.precision
+		.resolve(args.pixel_size(), vk.enabled_features().shader_f3264, args.fractal);
+	println!("fractal: {:?}, precision: {:?}", args.fractal, precision);
 
-	// buffers
-	let (w, h) = (2048, 2048);
-	let gpu_image = vk.storage_image((w, h), Format::R8G8B8A8Unorm);
-	let cpu_buffer = vk.cpu_accessible_buffer((w * h * 4) as usize);
-
-	// shader
-	mod cs {
-		vulkano_shaders::shader! {
-			ty: "compute",
-			// v6
-			path: "src/bin/mandelbrot/mandelbrot.glsl",
+	// Tiles are rendered at the supersampled size, which must fit in an image.
+	let (w, h) = (args.width, args.height);
+	let ss = args.supersample;
+	let max_size = vk.device().physical_device().limits().max_image_dimension_2d();
+	// The Lanczos filter reaches 3 pixels beyond each pixel. Tiles are rendered with that margin
+	// taken from their neighbours and cropped after filtering, so that the tile boundaries do not show.
+	let margin = if ss > 1 && args.filter == DownsampleFilter::Lanczos3 { 3 } else { 0 };
+	let tile_size = args.tile.min((max_size / ss).saturating_sub(2 * margin));
+	if tile_size == 0 {
+		fatal(format!("--supersample {} exceeds the maximum image size {} of this device", ss, max_size));
+	}
+	let tiles = tiles::tiles(w, h, tile_size);
+	let tile_count = tiles.iter().map(Vec::len).sum::<usize>();
+	if tile_count > 1 {
+		println!("{} tiles of up to {}x{} pixels", tile_count, tile_size, tile_size);
+		// Holding the whole picture would defeat the bounded memory of tiling.
+		if let OutputFormat::Image(format) = args.format {
+			fatal(format!(
+				"{:?} output needs the whole image in memory, which takes {} tiles: use png, tiff, exr or ppm, or a larger --tile",
+				format, tile_count
+			));
 		}
 	}
-	let shader = cs::Shader::load(vk.device()).unwrap();
 
-	// command
-	let compute_pipeline = Arc::new(ComputePipeline::new(vk.device(), &shader.main_entry_point(), &()).unwrap());
-	let set = Arc::new(
-		PersistentDescriptorSet::start(compute_pipeline.layout().descriptor_set_layout(0).unwrap().clone())
-			.add_image(gpu_image.clone())
-			.unwrap()
-			.build()
-			.unwrap(),
-	);
-
-	let local_size_x = 8;
-	let local_size_y = 8;
-	let local_size_z = 1;
-
-	let mut builder = vk.auto_command_buffer_builder();
-	builder
-		.dispatch(
-			[w / local_size_x, h / local_size_y, local_size_z],
-			compute_pipeline.clone(),
-			set.clone(),
-			(),
-		)
-		.unwrap()
-		.copy_image_to_buffer(gpu_image.clone(), cpu_buffer.clone())
-		.unwrap();
-	let command_buffer = builder.build().unwrap();
+	let renderer = render::Renderer::new(&vk, &args, precision).unwrap_or_else(|msg| fatal(msg));
+	let colorizer = color::Colorizer::new(&vk, &palette);
+	let downsample = Downsample::new(&vk, args.filter);
+	let mut output = Output::create(&args.output, args.format, args.pixels, w, h).unwrap_or_else(|msg| fatal(msg));
 	println!("init: {} ms", started.elapsed().as_secs_f32() * 1000.0);
 
-	// exec + transfer
+	// 8-bit colors are sRGB encoded: supersamples are kept linear, and encoded after filtering (see Downsample)
+	let sample_format = if ss > 1 && args.pixels == Format::R8G8B8A8Unorm {
+		Format::R16G16B16A16Sfloat
+	} else {
+		args.pixels
+	};
+
 	let started = now();
-	let finished = command_buffer.execute(vk.queue()).unwrap();
+	let render_tile = |builder: &mut AutoCommandBufferBuilder, tile: &Tile| {
+		let values = vk.storage_image((tile.width * ss, tile.height * ss), Format::R32Sfloat);
+		renderer.record(builder, values.clone(), tile.offset((w, h), ss));
+		values
+	};
+
+	// Equalization needs the histogram of the whole image, so with several tiles they are rendered twice.
+	let mut first_values = None;
+	let cdf = if args.equalize {
+		let mut builder = vk.auto_command_buffer_builder();
+		let histogram = colorizer.histogram(&vk, &mut builder, args.iterations);
+		for tile in tiles.iter().flatten() {
+			let values = render_tile(&mut builder, tile);
+			colorizer.record_histogram(&vk, &mut builder, values.clone(), histogram.clone());
+			if tile_count == 1 {
+				first_values = Some(values);
+			}
+			// one tile at a time, to bound memory
+			execute(&vk, builder);
+			builder = vk.auto_command_buffer_builder();
+		}
+		let cdf = colorizer.record_cumulative(&vk, &mut builder, histogram);
+		execute(&vk, builder);
+		Some(cdf)
+	} else {
+		None
+	};
+
+	// Encoding runs on other threads, only the time spent waiting for it is counted.
+	let mut encode_time = 0.0;
+	let pixel_size = image_size(args.pixels, [1, 1]);
+	// One band of full-width rows at a time: host memory grows with the width times the tile size.
+	let band_row = w as usize * pixel_size;
+	let mut band = Vec::new();
+	// Copy the rows of a finished tile, without the margin, into the band of its row of tiles,
+	// and write the band once its last tile is in.
+	let mut receive = |readback: Readback| {
+		readback.done.wait(None).unwrap();
+		let (tile, area) = (readback.tile, readback.area);
+		if tile.x == 0 {
+			band = vec![0u8; band_row * tile.height as usize];
+		}
+		let area_pixels = readback.staging.read().unwrap();
+		let area_row = area.width as usize * pixel_size;
+		let skip = (tile.y - area.y) as usize * area_row + (tile.x - area.x) as usize * pixel_size;
+		for (y, src) in area_pixels[skip..].chunks(area_row).take(tile.height as usize).enumerate() {
+			let src = &src[..tile.width as usize * pixel_size];
+			let start = y * band_row + tile.x as usize * pixel_size;
+			band[start..start + src.len()].copy_from_slice(src);
+		}
+		if tile.x + tile.width == w {
+			let started = now();
+			output.write_rows(&band).unwrap_or_else(|msg| fatal(msg));
+			encode_time += started.elapsed().as_secs_f32();
+		}
+	};
+
+	// Each tile is copied to the host on the transfer queue once its compute has finished,
+	// while the next tile is already dispatched: the host only waits for the oldest tile in flight.
+	let mut in_flight = VecDeque::new();
+	for tile in tiles.iter().flatten() {
+		let mut builder = vk.auto_command_buffer_builder();
+		let area = tile.expand(margin, (w, h));
+		let values = first_values.take().unwrap_or_else(|| render_tile(&mut builder, &area));
+		// color the samples, unless the output is the iteration counts themselves
+		let samples = if args.pixels == Format::R32Sfloat {
+			values
+		} else {
+			let samples = vk.storage_image((area.width * ss, area.height * ss), sample_format);
+			colorizer.record_colorize(&mut builder, values, samples.clone(), args.iterations, cdf.clone());
+			samples
+		};
+		// then filter them down to pixels
+		let pixels = if ss == 1 {
+			samples
+		} else {
+			let pixels = vk.storage_image((area.width, area.height), args.pixels);
+			downsample.record(&mut builder, samples, pixels.clone());
+			pixels
+		};
+
+		let staging = vk.image_buffer(&pixels);
+		let mut copy = vk.transfer_command_buffer_builder();
+		copy.copy_image_to_buffer_dimensions(pixels, staging.clone(), [0; 3], [area.width, area.height, 1], 0, 1, 0)
+			.unwrap();
+		let done = builder
+			.build()
+			.unwrap()
+			.execute(vk.queue())
+			.unwrap()
+			.then_signal_semaphore_and_flush()
+			.unwrap()
+			.then_execute(vk.transfer_queue(), copy.build().unwrap())
+			.unwrap()
+			.boxed()
+			.then_signal_fence_and_flush()
+			.unwrap();
+		in_flight.push_back(Readback {
+			tile: *tile,
+			area,
+			staging,
+			done,
+		});
+		if in_flight.len() == TILES_IN_FLIGHT {
+			receive(in_flight.pop_front().unwrap());
+		}
+	}
+	for readback in in_flight {
+		receive(readback);
+	}
+
+	let finish_started = now();
+	output.finish().unwrap_or_else(|msg| fatal(msg));
+	encode_time += finish_started.elapsed().as_secs_f32();
+	println!("compute + transfer: {} ms", (started.elapsed().as_secs_f32() - encode_time) * 1000.0);
+	println!("encode (waiting): {} ms", encode_time * 1000.0);
+}
+
+// Tiles rendered or being copied to the host at any time. Each holds its images and staging buffer,
+// so memory use grows with this.
+const TILES_IN_FLIGHT: usize = 2;
+
+// A tile on its way to the host: `staging` holds the pixels of `area`, the tile with its margin, once `done` is signaled.
+struct Readback {
+	tile: Tile,
+	area: Tile,
+	staging: Arc<CpuAccessibleBuffer<[u8]>>,
+	done: FenceSignalFuture<Box<dyn GpuFuture>>,
+}
+
+// Execute on the compute queue and wait for completion.
+fn execute(vk: &Interface, builder: AutoCommandBufferBuilder) {
+	let finished = builder.build().unwrap().execute(vk.queue()).unwrap();
 	finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
-	let buffer_content = cpu_buffer.read().unwrap(); // read is really just lock
-	println!("compute + transfer: {} ms", started.elapsed().as_secs_f32() * 1000.0);
+}
 
-	let started = now();
-	let image = ImageBuffer::<Rgba<u8>, _>::from_raw(w, h, &buffer_content[..]).unwrap();
-	image.save("image.png").expect("save image.png");
-	println!("encode: {} ms", started.elapsed().as_secs_f32() * 1000.0);
+fn fatal(msg: impl std::fmt::Display) -> ! {
+	eprintln!("{}", msg);
+	std::process::exit(1)
 }
 
 fn now() -> std::time::Instant {
@@ -1,27 +1,108 @@
 #version 450
 
-layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+// Escape-time fractals: the Mandelbrot set and its relatives, selected by specialization constants
+// (see fractal.rs), as well as their Julia sets.
+//
+// Compiled with F64 defined for devices with shaderFloat64.
+#ifdef F64
+#define real double
+#define vec2r dvec2
+#else
+#define real float
+#define vec2r vec2
+#endif
 
-layout(set = 0, binding = 0, rgba8) uniform writeonly image2D img;
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+#define QUADRATIC 0
+#define BURNING_SHIP 1
+#define TRICORN 2
+#define MULTIBROT 3
+
+// iteration z' = f(z) + c, one of the above
+layout(constant_id = 3) const uint formula = QUADRATIC;
+// exponent for MULTIBROT
+layout(constant_id = 4) const uint power = 2;
+// Julia set: z starts at the pixel and c is fixed, instead of z starting at 0 and c being the pixel.
+layout(constant_id = 5) const bool julia = false;
+
+// smooth iteration count, see below
+layout(set = 0, binding = 0, r32f) uniform writeonly image2D img;
+
+layout(push_constant) uniform PushConstants {
+    // complex coordinate of the image center
+    vec2r center;
+    // c of the Julia set
+    vec2r julia_c;
+    // position of this image's center relative to the view center, in pixels, for rendering in tiles
+    vec2r tile_offset;
+    // distance between pixels in the complex plane
+    real pixel_size;
+    uint iterations;
+} params;
+
+// Continuous iteration count in [i, i + 1) for a point that escaped at iteration i with value z,
+// or the iteration limit for points that did not escape.
+float smooth_count(uint i, vec2r z) {
+    if (i == params.iterations) {
+        return float(i);
+    }
+    // |z| is between the escape radius 4 and about 4^degree, so this fraction goes from 1 to 0.
+    float degree = formula == MULTIBROT ? float(power) : 2.0;
+    float frac = 1.0 - log(log(float(length(z))) / log(4.0)) / log(degree);
+    return float(i) + clamp(frac, 0.0, 0.999);
+}
+
+vec2r cmul(vec2r a, vec2r b) {
+    return vec2r(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// z^n by repeated squaring
+vec2r cpow(vec2r z, uint n) {
+    vec2r result = vec2r(1.0, 0.0);
+    for (; n > 0; n >>= 1) {
+        if ((n & 1) != 0) {
+            result = cmul(result, z);
+        }
+        z = cmul(z, z);
+    }
+    return result;
+}
+
+vec2r iterate(vec2r z, vec2r c) {
+    if (formula == MULTIBROT) {
+        return cpow(z, power) + c;
+    }
+    if (formula == BURNING_SHIP) {
+        z = abs(z);
+    } else if (formula == TRICORN) {
+        z.y = -z.y;
+    }
+    return vec2r(
+        z.x * z.x - z.y * z.y + c.x,
+        z.y * z.x + z.x * z.y + c.y
+    );
+}
 
 void main() {
-    vec2 norm_coordinates = (gl_GlobalInvocationID.xy + vec2(0.5)) / vec2(imageSize(img));
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(img))))) {
+        return;
+    }
 
-    vec2 c = (norm_coordinates - vec2(0.5)) * 2.0 - vec2(1.0, 0.0);
+    vec2r offset = (vec2r(gl_GlobalInvocationID.xy) + vec2r(0.5)) - vec2r(imageSize(img)) / 2.0 + params.tile_offset;
+    vec2r point = params.center + offset * params.pixel_size;
+    vec2r z = julia ? point : vec2r(0.0, 0.0);
+    vec2r c = julia ? params.julia_c : point;
 
-    vec2 z = vec2(0.0, 0.0);
-    float i;
-    for (i = 0.0; i < 1.0; i += (1.0 / 64.0)) {
-        z = vec2(
-            z.x * z.x - z.y * z.y + c.x,
-            z.y * z.x + z.x * z.y + c.y
-        );
+    uint i;
+    for (i = 0; i < params.iterations; i++) {
+        z = iterate(z, c);
 
         if (length(z) > 4.0) {
             break;
         }
     }
 
-    vec4 to_write = vec4(vec3(i), 1.0);
-    imageStore(img, ivec2(gl_GlobalInvocationID.xy), to_write);
-}
\ No newline at end of file
+    imageStore(img, ivec2(gl_GlobalInvocationID.xy), vec4(smooth_count(i, z), 0.0, 0.0, 0.0));
+}
@@ -0,0 +1,104 @@
+//! Writing the output image band by band, as tiles are rendered.
+
+use image::{ImageFormat, RgbaImage};
+use std::path::{Path, PathBuf};
+use vulkan_playground::{Export, ExportLayout, FileFormat, Format};
+
+/// File format of the output image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+	/// Encoded on worker threads while the next tiles render.
+	Export(FileFormat),
+	/// Any other format the image crate writes, encoded from the whole image at once.
+	/// Only for images rendered in a single tile, so that tiling bounds memory use.
+	Image(ImageFormat),
+}
+
+impl OutputFormat {
+	/// The format for a name or file extension, like "png" or "jpeg".
+	pub fn parse(name: &str) -> Option<Self> {
+		use ImageFormat::*;
+		let name = name.to_lowercase();
+		let image_format = || {
+			[Jpeg, Bmp, Tga, Farbfeld]
+				.iter()
+				.copied()
+				.find(|f| f.extensions_str().contains(&name.as_str()) || format!("{:?}", f).to_lowercase() == name)
+		};
+		FileFormat::from_extension(&name)
+			.map(OutputFormat::Export)
+			.or_else(|| image_format().map(OutputFormat::Image))
+	}
+
+	/// The format for a path's extension.
+	pub fn from_path(path: &Path) -> Option<Self> {
+		Self::parse(path.extension()?.to_str()?)
+	}
+}
+
+/// Image file receiving rows of pixels from top to bottom.
+pub enum Output {
+	Export(Export),
+	/// Only RGBA8 pixels, of an image of a single tile.
+	Buffered {
+		path: PathBuf,
+		format: ImageFormat,
+		image: RgbaImage,
+		next_row: u32,
+	},
+}
+
+impl Output {
+	/// Create the file for a `width` x `height` image of `pixels`, in the format they are read back from the GPU.
+	pub fn create(path: &Path, format: OutputFormat, pixels: Format, width: u32, height: u32) -> Result<Self, String> {
+		let err = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+		Ok(match format {
+			OutputFormat::Export(format) => {
+				let layout = ExportLayout::for_format(pixels, width, height).ok_or_else(|| err(&format!("cannot export {:?} pixels", pixels)))?;
+				Output::Export(Export::with_format(path, format, layout).map_err(|e| err(&e))?)
+			}
+			OutputFormat::Image(format) if pixels != Format::R8G8B8A8Unorm => {
+				return Err(err(&format!("{:?} only takes 8 bit RGBA pixels", format)));
+			}
+			OutputFormat::Image(format) => Output::Buffered {
+				path: path.to_owned(),
+				format,
+				image: RgbaImage::new(width, height),
+				next_row: 0,
+			},
+		})
+	}
+
+	/// Append whole rows of pixels.
+	pub fn write_rows(&mut self, rows: &[u8]) -> Result<(), String> {
+		match self {
+			Output::Export(export) => export.write_rows(rows).map_err(|e| e.to_string()),
+			Output::Buffered { image, next_row, .. } => {
+				let row_len = 4 * image.width() as usize;
+				let start = *next_row as usize * row_len;
+				let data: &mut [u8] = image;
+				data.get_mut(start..start + rows.len()).ok_or("too many rows")?.copy_from_slice(rows);
+				*next_row += (rows.len() / row_len) as u32;
+				Ok(())
+			}
+		}
+	}
+
+	/// Complete the file. All rows must have been written.
+	pub fn finish(self) -> Result<(), String> {
+		match self {
+			Output::Export(export) => export.finish().map_err(|e| e.to_string()),
+			Output::Buffered {
+				path,
+				format,
+				image,
+				next_row,
+			} => {
+				if next_row != image.height() {
+					return Err(format!("{}: {} of {} rows written", path.display(), next_row, image.height()));
+				}
+				image.save_with_format(&path, format).map_err(|e| format!("{}: {}", path.display(), e))
+			}
+		}
+	}
+}
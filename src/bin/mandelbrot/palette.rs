@@ -0,0 +1,114 @@
+//! Color gradients for the colorize pass.
+
+use std::fs;
+
+/// Names accepted by `Palette::builtin`.
+pub const BUILTIN: &[&str] = &["grey", "ultra", "fire", "ocean"];
+
+/// A gradient through color stops at positions from 0 to 1.
+///
+/// Palette files list one stop per line, as a position followed by a hex color:
+///
+/// ```text
+/// # blue to white
+/// 0.0  #000764
+/// 0.5  #206bcb
+/// 1.0  #ffffff
+/// ```
+///
+/// Lines starting with `#` are comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+	// sorted by position
+	stops: Vec<(f32, [f32; 3])>,
+}
+
+impl Palette {
+	pub fn builtin(name: &str) -> Option<Self> {
+		let text = match name {
+			"grey" => "0 #000000\n1 #ffffff",
+			"ultra" => "0 #000764\n0.16 #206bcb\n0.42 #edffff\n0.6425 #ffaa00\n0.8575 #000200\n1 #000764",
+			"fire" => "0 #000000\n0.33 #b30000\n0.66 #ffb300\n1 #ffffff",
+			"ocean" => "0 #000020\n0.5 #0080c0\n1 #e0ffff",
+			_ => return None,
+		};
+		Some(Self::parse(text).unwrap())
+	}
+
+	/// A built-in palette, or else a palette file.
+	pub fn load(name_or_path: &str) -> Result<Self, String> {
+		if let Some(p) = Self::builtin(name_or_path) {
+			return Ok(p);
+		}
+		let text = fs::read_to_string(name_or_path).map_err(|e| {
+			format!(
+				"palette {:?}: not one of {} and cannot read file: {}",
+				name_or_path,
+				BUILTIN.join(", "),
+				e
+			)
+		})?;
+		Self::parse(&text).map_err(|e| format!("palette {}: {}", name_or_path, e))
+	}
+
+	/// Parse a stop list in the palette file format.
+	pub fn parse(text: &str) -> Result<Self, String> {
+		let mut stops = Vec::new();
+		for (i, line) in text.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			let err = || format!("line {}: expected position and #rrggbb color, got {:?}", i + 1, line);
+			let mut fields = line.split_whitespace();
+			let (pos, color) = match (fields.next(), fields.next(), fields.next()) {
+				(Some(pos), Some(color), None) => (pos, color),
+				_ => return Err(err()),
+			};
+			let pos: f32 = pos.parse().map_err(|_| err())?;
+			if !(0.0..=1.0).contains(&pos) {
+				return Err(format!("line {}: position {} outside 0..1", i + 1, pos));
+			}
+			stops.push((pos, parse_hex(color).ok_or_else(err)?));
+		}
+		if stops.is_empty() {
+			return Err("no color stops".to_string());
+		}
+		stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+		Ok(Self { stops })
+	}
+
+	/// Color at `t` in 0..1, interpolated between the stops and clamped outside them.
+	pub fn color(&self, t: f32) -> [f32; 3] {
+		let next = self.stops.iter().position(|&(pos, _)| pos > t).unwrap_or(self.stops.len());
+		if next == 0 {
+			return self.stops[0].1;
+		}
+		if next == self.stops.len() {
+			return self.stops[next - 1].1;
+		}
+		let ((p0, c0), (p1, c1)) = (self.stops[next - 1], self.stops[next]);
+		let f = (t - p0) / (p1 - p0);
+		[0, 1, 2].map(|i| c0[i] + (c1[i] - c0[i]) * f)
+	}
+
+	/// `n` evenly spaced RGBA colors, as uploaded to the colorize pass.
+	pub fn lut(&self, n: usize) -> Vec<[f32; 4]> {
+		(0..n)
+			.map(|i| {
+				let [r, g, b] = self.color(i as f32 / (n - 1).max(1) as f32);
+				[r, g, b, 1.0]
+			})
+			.collect()
+	}
+}
+
+// `#rrggbb` as RGB in 0..1.
+fn parse_hex(s: &str) -> Option<[f32; 3]> {
+	let hex = s.strip_prefix('#')?;
+	if hex.len() != 6 || !hex.is_ascii() {
+		return None;
+	}
+	let channel = |i: usize| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok().map(|v| v as f32 / 255.0);
+	Some([channel(0)?, channel(1)?, channel(2)?])
+}
@@ -0,0 +1,82 @@
+#version 450
+
+// Perturbation theory: each pixel iterates its offset dz from a high-precision reference orbit Z
+// at the image center, so only the (small) deltas need to fit in float or double precision.
+//
+// Compiled with F64 defined for devices with shaderFloat64.
+#ifdef F64
+#define real double
+#define vec2r dvec2
+#else
+#define real float
+#define vec2r vec2
+#endif
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+// smooth iteration count, see below
+layout(set = 0, binding = 0, r32f) uniform writeonly image2D img;
+
+// reference orbit Z_0 = 0, Z_1, ..., computed on the CPU
+layout(set = 0, binding = 1) readonly buffer Orbit {
+    vec2r orbit[];
+};
+
+layout(push_constant) uniform PushConstants {
+    // position of this image's center relative to the reference point, in pixels, for rendering in tiles
+    vec2r tile_offset;
+    // distance between pixels in the complex plane
+    real pixel_size;
+    uint iterations;
+} params;
+
+vec2r cmul(vec2r a, vec2r b) {
+    return vec2r(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// Continuous iteration count in [i, i + 1) for a point that escaped at iteration i with value z,
+// or the iteration limit for points that did not escape.
+float smooth_count(uint i, vec2r z) {
+    if (i == params.iterations) {
+        return float(i);
+    }
+    // |z| is between the escape radius 4 and about 4², so this fraction goes from 1 to 0.
+    float frac = 1.0 - log2(log(float(length(z))) / log(4.0));
+    return float(i) + clamp(frac, 0.0, 0.999);
+}
+
+void main() {
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(img))))) {
+        return;
+    }
+
+    vec2r offset = (vec2r(gl_GlobalInvocationID.xy) + vec2r(0.5)) - vec2r(imageSize(img)) / 2.0 + params.tile_offset;
+    vec2r dc = offset * params.pixel_size;
+
+    uint last = uint(orbit.length()) - 1;
+    vec2r dz = vec2r(0.0, 0.0);
+    vec2r z = vec2r(0.0, 0.0);
+    uint m = 0;
+    uint i;
+    for (i = 0; i < params.iterations; i++) {
+        // z = Z + dz  =>  dz' = 2 Z dz + dz^2 + dc
+        dz = 2.0 * cmul(orbit[m], dz) + cmul(dz, dz) + dc;
+        m++;
+
+        z = orbit[m] + dz;
+        real r2 = dot(z, z);
+        if (r2 > 16.0) {
+            break;
+        }
+
+        // Rebase onto the start of the orbit when z gets closer to 0 than to the reference,
+        // or when the reference escaped. This avoids glitches where dz loses precision.
+        if (r2 < dot(dz, dz) || m == last) {
+            dz = z;
+            m = 0;
+        }
+    }
+
+    imageStore(img, ivec2(gl_GlobalInvocationID.xy), vec4(smooth_count(i, z), 0.0, 0.0, 0.0));
+}
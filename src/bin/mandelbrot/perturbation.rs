@@ -0,0 +1,234 @@
+//! Reference orbit for perturbation rendering (see perturbation.glsl).
+//!
+//! Beyond f64 precision, the image center must be known to more digits than any float type holds,
+//! so the reference orbit is iterated in arbitrary-precision fixed point.
+
+use std::cmp::Ordering;
+
+/// Smallest pixel sizes that perturbation resolves, with float and with double deltas.
+/// Deltas below the smallest normal number lose precision, or are flushed to zero by most GPUs,
+/// so these keep a margin above it for the pixels next to the center.
+pub const MIN_PIXEL_SIZE_F32: f64 = 1e-36;
+pub const MIN_PIXEL_SIZE_F64: f64 = 1e-300;
+
+/// `Err` if deltas of `pixel_size` are too small for double precision, or for float unless `f64_deltas`.
+pub fn check_pixel_size(pixel_size: f64, f64_deltas: bool) -> Result<(), String> {
+	let (min, deltas) = if f64_deltas {
+		(MIN_PIXEL_SIZE_F64, "double")
+	} else {
+		(MIN_PIXEL_SIZE_F32, "float (the device has no shaderFloat64)")
+	};
+	if pixel_size < min {
+		return Err(format!(
+			"perturbation precision: pixel size {:e} is below {:e}, the smallest that {} deltas resolve",
+			pixel_size, min, deltas
+		));
+	}
+	Ok(())
+}
+
+/// Reference orbit Z_0 = 0, Z_n+1 = Z_n² + C at the center `C`, given as decimal strings.
+///
+/// The orbit is computed with enough bits to resolve `pixel_size` and rounded to f64 afterwards:
+/// only the per-pixel deltas need to be small, not the orbit itself.
+/// It stops after `iterations` steps or as soon as |Z| > 4.
+pub fn reference_orbit(center: &[String; 2], pixel_size: f64, iterations: u32) -> Result<Vec<[f64; 2]>, String> {
+	// 64 bits of headroom below the pixel size.
+	let bits = (-pixel_size.log2()).max(0.0).ceil() as usize + 64;
+	let frac = bits.div_ceil(32);
+	let parse = |s: &String| Fixed::parse(s, frac).ok_or_else(|| format!("invalid coordinate {:?}", s));
+	let (cx, cy) = (parse(&center[0])?, parse(&center[1])?);
+
+	let mut orbit = vec![[0.0, 0.0]];
+	let (mut x, mut y) = (Fixed::zero(frac), Fixed::zero(frac));
+	for _ in 0..iterations {
+		let xy = x.mul(&y);
+		x = x.mul(&x).sub(&y.mul(&y)).add(&cx);
+		y = xy.add(&xy).add(&cy);
+		let z = [x.to_f64(), y.to_f64()];
+		orbit.push(z);
+		if z[0] * z[0] + z[1] * z[1] > 16.0 {
+			break;
+		}
+	}
+	Ok(orbit)
+}
+
+/// Signed fixed-point number with a 32-bit integer part and `32 * frac` fraction bits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixed {
+	neg: bool,
+	// Magnitude, least significant limb first. The last limb is the integer part.
+	mag: Vec<u32>,
+}
+
+impl Fixed {
+	pub fn zero(frac: usize) -> Self {
+		Self {
+			neg: false,
+			mag: vec![0; frac + 1],
+		}
+	}
+
+	/// Parse a decimal number like `-0.743643887037158704752191506114774`, `1.5e-3` or `2`.
+	pub fn parse(s: &str, frac: usize) -> Option<Self> {
+		let s = s.trim();
+		let (neg, s) = match s.strip_prefix('-') {
+			Some(rest) => (true, rest),
+			None => (false, s.strip_prefix('+').unwrap_or(s)),
+		};
+		let (mantissa, exp) = match s.find(&['e', 'E'][..]) {
+			Some(i) => (&s[..i], s[i + 1..].parse::<i64>().ok()?),
+			None => (s, 0),
+		};
+		let (int, fraction) = match mantissa.find('.') {
+			Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+			None => (mantissa, ""),
+		};
+		let digits: Vec<u8> = int.bytes().chain(fraction.bytes()).collect();
+		if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+			return None;
+		}
+
+		// Move the decimal point by the exponent, then split into integer and fraction digits.
+		let point = int.len() as i64 + exp;
+		if point.abs() > 100_000 {
+			return None;
+		}
+		let mut digits: Vec<u32> = digits.iter().map(|d| (d - b'0') as u32).collect();
+		if point < 0 {
+			digits.splice(0..0, vec![0; (-point) as usize]);
+		}
+		let point = point.max(0) as usize;
+		if digits.len() < point {
+			digits.resize(point, 0);
+		}
+		let (int, fraction) = digits.split_at(point);
+
+		let mut int_value: u64 = 0;
+		for &d in int {
+			int_value = int_value * 10 + d as u64;
+			if int_value >= 1 << 32 {
+				return None;
+			}
+		}
+
+		// 0.d1d2d3... = (d1 + (d2 + (d3 + ...) / 10) / 10) / 10
+		let mut x = Self::zero(frac);
+		for &d in fraction.iter().rev() {
+			x.mag[frac] = d;
+			x.div_small(10);
+		}
+		x.mag[frac] = int_value as u32;
+		x.neg = neg && !x.is_zero();
+		Some(x)
+	}
+
+	pub fn to_f64(&self) -> f64 {
+		let frac = self.mag.len() - 1;
+		let v: f64 = self
+			.mag
+			.iter()
+			.enumerate()
+			.map(|(i, &limb)| limb as f64 * 4294967296f64.powi(i as i32 - frac as i32))
+			.sum();
+		if self.neg {
+			-v
+		} else {
+			v
+		}
+	}
+
+	pub fn add(&self, rhs: &Self) -> Self {
+		if self.neg == rhs.neg {
+			return Self {
+				neg: self.neg,
+				mag: add_mag(&self.mag, &rhs.mag),
+			};
+		}
+		match cmp_mag(&self.mag, &rhs.mag) {
+			Ordering::Less => Self {
+				neg: rhs.neg,
+				mag: sub_mag(&rhs.mag, &self.mag),
+			},
+			_ => {
+				let mag = sub_mag(&self.mag, &rhs.mag);
+				let neg = self.neg && mag.iter().any(|&l| l != 0);
+				Self { neg, mag }
+			}
+		}
+	}
+
+	pub fn sub(&self, rhs: &Self) -> Self {
+		let neg_rhs = Self {
+			neg: !rhs.neg && !rhs.is_zero(),
+			mag: rhs.mag.clone(),
+		};
+		self.add(&neg_rhs)
+	}
+
+	/// Product, truncated to the precision of `self`. Panics if the integer part overflows.
+	pub fn mul(&self, rhs: &Self) -> Self {
+		let n = self.mag.len();
+		assert_eq!(n, rhs.mag.len(), "fixed point precision mismatch");
+		let mut p = vec![0u32; 2 * n];
+		for (i, &a) in self.mag.iter().enumerate() {
+			let mut carry = 0u64;
+			for (j, &b) in rhs.mag.iter().enumerate() {
+				let t = a as u64 * b as u64 + p[i + j] as u64 + carry;
+				p[i + j] = t as u32;
+				carry = t >> 32;
+			}
+			p[i + n] = carry as u32;
+		}
+		assert_eq!(p[2 * n - 1], 0, "fixed point overflow");
+		let mag = p[n - 1..2 * n - 1].to_vec();
+		let neg = self.neg != rhs.neg && mag.iter().any(|&l| l != 0);
+		Self { neg, mag }
+	}
+
+	fn is_zero(&self) -> bool {
+		self.mag.iter().all(|&l| l == 0)
+	}
+
+	fn div_small(&mut self, d: u32) {
+		let mut rem = 0u64;
+		for limb in self.mag.iter_mut().rev() {
+			let cur = (rem << 32) | *limb as u64;
+			*limb = (cur / d as u64) as u32;
+			rem = cur % d as u64;
+		}
+	}
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+	a.iter().rev().cmp(b.iter().rev())
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+	let mut carry = 0u64;
+	let sum = a
+		.iter()
+		.zip(b)
+		.map(|(&a, &b)| {
+			let t = a as u64 + b as u64 + carry;
+			carry = t >> 32;
+			t as u32
+		})
+		.collect();
+	assert_eq!(carry, 0, "fixed point overflow");
+	sum
+}
+
+// a - b, for a >= b.
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+	let mut borrow = 0i64;
+	a.iter()
+		.zip(b)
+		.map(|(&a, &b)| {
+			let t = a as i64 - b as i64 - borrow;
+			borrow = (t < 0) as i64;
+			t.rem_euclid(1 << 32) as u32
+		})
+		.collect()
+}
@@ -0,0 +1,183 @@
+//! Dispatch of the fractal kernels, one per `Precision`.
+
+use crate::args::{Args, Precision};
+use crate::perturbation::{check_pixel_size, reference_orbit};
+use vulkano::sync::GpuFuture;
+
+use vulkan_playground::*;
+
+mod cs {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/mandelbrot.glsl",
+	}
+}
+
+mod cs_f64 {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/mandelbrot.glsl",
+		define: [("F64", "1")],
+	}
+}
+
+mod perturbation_f32 {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/perturbation.glsl",
+	}
+}
+
+mod perturbation_f64 {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/perturbation.glsl",
+		define: [("F64", "1")],
+	}
+}
+
+/// Features to enable when available: `shaderFloat64` for the f64 kernels.
+pub fn optional_features() -> Features {
+	Features {
+		shader_f3264: true,
+		..Features::none()
+	}
+}
+
+/// The kernel for one `Precision`, with its parameters.
+enum Variant {
+	F32(Kernel<cs::ty::PushConstants>),
+	F64(Kernel<cs_f64::ty::PushConstants>),
+	PerturbationF32(Kernel<perturbation_f32::ty::PushConstants>, Arc<DeviceLocalBuffer<[Vec2]>>),
+	PerturbationF64(Kernel<perturbation_f64::ty::PushConstants>, Arc<DeviceLocalBuffer<[[f64; 2]]>>),
+}
+
+/// Renders smooth iteration counts of the view described by `Args`, in one image or in tiles.
+pub struct Renderer {
+	variant: Variant,
+	center: [f64; 2],
+	julia_c: [f64; 2],
+	pixel_size: f64,
+	iterations: u32,
+}
+
+impl Renderer {
+	/// Load the kernel for `precision`, which must not be `Auto`.
+	/// For perturbation, this computes the reference orbit and uploads it.
+	pub fn new(vk: &Interface, args: &Args, precision: Precision) -> Result<Self, String> {
+		let f64_supported = vk.enabled_features().shader_f3264;
+		let fractal = args.fractal;
+		let spec_f32 = cs::SpecializationConstants {
+			formula: fractal.formula(),
+			power: fractal.power(),
+			julia: fractal.julia_c().is_some() as u32,
+			..Default::default()
+		};
+		let spec_f64 = cs_f64::SpecializationConstants {
+			formula: fractal.formula(),
+			power: fractal.power(),
+			julia: fractal.julia_c().is_some() as u32,
+			..Default::default()
+		};
+
+		let variant = match precision {
+			Precision::Auto => panic!("render: unresolved precision"),
+			Precision::F32 => {
+				let shader = cs::Shader::load(vk.device()).unwrap();
+				Variant::F32(Kernel::with_specialization(vk, &shader.main_entry_point(), spec_f32))
+			}
+			Precision::F64 => {
+				if !f64_supported {
+					return Err(format!("f64 precision: {} does not support shaderFloat64", vk.info()));
+				}
+				let shader = cs_f64::Shader::load(vk.device()).unwrap();
+				Variant::F64(Kernel::with_specialization(vk, &shader.main_entry_point(), spec_f64))
+			}
+			Precision::Perturbation => {
+				if !fractal.supports_perturbation() {
+					return Err(format!("perturbation precision: not supported for {:?}", fractal));
+				}
+				check_pixel_size(args.pixel_size(), f64_supported)?;
+				let orbit = reference_orbit(&args.center, args.pixel_size(), args.iterations)?;
+				let usage = BufferUsage {
+					storage_buffer: true,
+					..BufferUsage::none()
+				};
+				// Deltas in double precision when available, which reaches far deeper zooms than float.
+				let (variant, uploaded) = if f64_supported {
+					let shader = perturbation_f64::Shader::load(vk.device()).unwrap();
+					let (orbit, uploaded) = vk.upload(&orbit, usage);
+					(
+						Variant::PerturbationF64(Kernel::new(vk, &shader.main_entry_point()), orbit),
+						uploaded.boxed(),
+					)
+				} else {
+					let shader = perturbation_f32::Shader::load(vk.device()).unwrap();
+					let orbit: Vec<Vec2> = orbit.iter().map(|z| Vec2::new(z[0] as f32, z[1] as f32)).collect();
+					let (orbit, uploaded) = vk.upload(&orbit, usage);
+					(
+						Variant::PerturbationF32(Kernel::new(vk, &shader.main_entry_point()), orbit),
+						uploaded.boxed(),
+					)
+				};
+				uploaded.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+				variant
+			}
+		};
+
+		Ok(Self {
+			variant,
+			center: args.center_f64(),
+			julia_c: fractal.julia_c().unwrap_or([0.0; 2]),
+			pixel_size: args.pixel_size(),
+			iterations: args.iterations,
+		})
+	}
+
+	/// Record a dispatch that renders into `image` (R32Sfloat),
+	/// a tile whose center is `tile_offset` pixels away from the center of the view (see `Tile::offset`).
+	pub fn record(&self, builder: &mut AutoCommandBufferBuilder, image: Arc<StorageImage<Format>>, tile_offset: [f64; 2]) {
+		let [w, h] = image.dimensions().width_height();
+		let size = [w, h, 1];
+		let (center, julia_c, pixel_size, iterations) = (self.center, self.julia_c, self.pixel_size, self.iterations);
+		let to_f32 = |v: [f64; 2]| [v[0] as f32, v[1] as f32];
+		match &self.variant {
+			Variant::F32(kernel) => {
+				let params = cs::ty::PushConstants {
+					center: to_f32(center),
+					julia_c: to_f32(julia_c),
+					tile_offset: to_f32(tile_offset),
+					pixel_size: pixel_size as f32,
+					iterations,
+				};
+				kernel.record_dispatch(builder, size, (image,), params);
+			}
+			Variant::F64(kernel) => {
+				let params = cs_f64::ty::PushConstants {
+					center,
+					julia_c,
+					tile_offset,
+					pixel_size,
+					iterations,
+				};
+				kernel.record_dispatch(builder, size, (image,), params);
+			}
+			Variant::PerturbationF32(kernel, orbit) => {
+				let params = perturbation_f32::ty::PushConstants {
+					tile_offset: to_f32(tile_offset),
+					pixel_size: pixel_size as f32,
+					iterations,
+				};
+				kernel.record_dispatch(builder, size, (image, orbit.clone()), params);
+			}
+			Variant::PerturbationF64(kernel, orbit) => {
+				let params = perturbation_f64::ty::PushConstants {
+					tile_offset,
+					pixel_size,
+					iterations,
+				};
+				kernel.record_dispatch(builder, size, (image, orbit.clone()), params);
+			}
+		}
+	}
+}
@@ -0,0 +1,44 @@
+//! Splitting the output into tiles that fit in device limits and memory.
+//!
+//! The tiles of a row are gathered into a band of full-width rows for the encoder, since PNG and TIFF
+//! compress whole rows. So memory use is O(width × tile size) on the host, and O(tile size²) on the device.
+
+/// A rectangle of the output image, in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tile {
+	pub x: u32,
+	pub y: u32,
+	pub width: u32,
+	pub height: u32,
+}
+
+impl Tile {
+	/// Position of the tile center relative to the center of a `full_width` x `full_height` image,
+	/// in pixels scaled by `scale`.
+	pub fn offset(&self, (full_width, full_height): (u32, u32), scale: u32) -> [f64; 2] {
+		let center = |start: u32, len: u32, full: u32| (start as f64 + len as f64 / 2.0 - full as f64 / 2.0) * scale as f64;
+		[center(self.x, self.width, full_width), center(self.y, self.height, full_height)]
+	}
+
+	/// The tile grown by `margin` pixels on each side, but not beyond a `full_width` x `full_height` image.
+	pub fn expand(&self, margin: u32, (full_width, full_height): (u32, u32)) -> Tile {
+		let x = self.x.saturating_sub(margin);
+		let y = self.y.saturating_sub(margin);
+		Tile {
+			x,
+			y,
+			width: (self.x + self.width + margin).min(full_width) - x,
+			height: (self.y + self.height + margin).min(full_height) - y,
+		}
+	}
+}
+
+/// Tiles of at most `size` x `size` pixels covering a `width` x `height` image,
+/// as rows of tiles from top to bottom, each from left to right.
+pub fn tiles(width: u32, height: u32, size: u32) -> Vec<Vec<Tile>> {
+	assert!(size > 0, "tiles: zero tile size");
+	let spans = |len: u32| (0..len).step_by(size as usize).map(move |start| (start, size.min(len - start)));
+	spans(height)
+		.map(|(y, h)| spans(width).map(|(x, w)| Tile { x, y, width: w, height: h }).collect())
+		.collect()
+}
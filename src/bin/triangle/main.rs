@@ -20,6 +20,7 @@
 // what a vertex or a shader is.
 
 use std::sync::Arc;
+use vulkan_playground::{DeviceSelection, UVec2, Vec2};
 use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
 use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
 use vulkano::device::Queue;
@@ -50,7 +51,7 @@ fn main() {
 	let vertex_buffer = {
 		#[derive(Default, Debug, Clone)]
 		struct Vertex {
-			position: [f32; 2],
+			position: Vec2,
 		}
 		vulkano::impl_vertex!(Vertex, position);
 
@@ -59,9 +60,15 @@ fn main() {
 			BufferUsage::all(),
 			false,
 			[
-				Vertex { position: [-0.5, -0.25] },
-				Vertex { position: [0.0, 0.5] },
-				Vertex { position: [0.25, -0.1] },
+				Vertex {
+					position: Vec2::new(-0.5, -0.25),
+				},
+				Vertex {
+					position: Vec2::new(0.0, 0.5),
+				},
+				Vertex {
+					position: Vec2::new(0.25, -0.1),
+				},
 			]
 			.iter()
 			.cloned(),
@@ -349,10 +356,11 @@ fn instance_win() -> Arc<Instance> {
 }
 
 // Find and initialze a vulkan physical device.
-// TODO: pick suitable device when multiple are available
-fn physical(instance: &Arc<Instance>) -> PhysicalDevice {
-	let physical = PhysicalDevice::enumerate(instance).next().unwrap();
-	println!("physical device: {} ({:?})", physical.name(), physical.ty());
+// Uses the same selection policy as `Interface`, including the VK_PLAYGROUND_DEVICE override.
+fn physical(instance: &Arc<Instance>) -> PhysicalDevice<'_> {
+	let selection = DeviceSelection::from_env().unwrap_or_default();
+	let physical = selection.select(instance).unwrap();
+	println!("physical device: {} ({:?}, {})", physical.name(), physical.ty(), selection);
 	physical
 }
 
@@ -473,8 +481,8 @@ fn window_size_dependent_setup(
 	let dimensions = images[0].dimensions();
 
 	let viewport = Viewport {
-		origin: [0.0, 0.0],
-		dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+		origin: Vec2::ZERO.into(),
+		dimensions: Vec2::from(UVec2::from(dimensions)).into(),
 		depth_range: 0.0..1.0,
 	};
 	dynamic_state.viewports = Some(vec![viewport]);
@@ -0,0 +1,97 @@
+use super::*;
+
+pub use bytemuck::Pod;
+pub use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer, DeviceLocalBuffer};
+
+use vulkano::buffer::cpu_pool::CpuBufferPoolSubbuffer;
+use vulkano::buffer::{CpuBufferPool, TypedBufferAccess};
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::memory::pool::StdMemoryPool;
+use vulkano::sync::GpuFuture;
+
+/// Typed host-visible buffers.
+///
+/// `CpuAccessibleBuffer::read` and `write` lock the contents as `[T]`, so no byte arithmetic is needed.
+impl Interface {
+	/// Zero-initialized buffer of `len` elements.
+	pub fn cpu_buffer<T: Pod + Send + Sync>(&self, len: usize, usage: BufferUsage) -> Arc<CpuAccessibleBuffer<[T]>> {
+		self.cpu_buffer_from_iter((0..len).map(|_| T::zeroed()), usage)
+	}
+
+	pub fn cpu_buffer_from_slice<T: Pod + Send + Sync>(&self, data: &[T], usage: BufferUsage) -> Arc<CpuAccessibleBuffer<[T]>> {
+		self.cpu_buffer_from_iter(data.iter().copied(), usage)
+	}
+
+	pub fn cpu_buffer_from_iter<T, I>(&self, data: I, usage: BufferUsage) -> Arc<CpuAccessibleBuffer<[T]>>
+	where
+		T: Pod + Send + Sync,
+		I: ExactSizeIterator<Item = T>,
+	{
+		CpuAccessibleBuffer::from_iter(self.device(), usage, false, data).unwrap()
+	}
+}
+
+/// Device-local buffers, for data that stays on the GPU between dispatches.
+///
+/// Transfers go through a host-visible staging buffer and run on the transfer queue.
+impl Interface {
+	/// Buffer of `len` elements in device memory, not accessible from the host.
+	/// Transfer usage is always added so that `upload` and `download` work.
+	pub fn device_local_buffer<T: Pod + Send + Sync>(&self, len: usize, usage: BufferUsage) -> Arc<DeviceLocalBuffer<[T]>> {
+		let usage = usage | BufferUsage::transfer_source() | BufferUsage::transfer_destination();
+		DeviceLocalBuffer::array(self.device(), len, usage, self.queue_families()).unwrap()
+	}
+
+	/// Copy `data` into a new device-local buffer.
+	/// The buffer holds the data once the returned future completes, so chain work on it with `then_execute`.
+	pub fn upload<T: Pod + Send + Sync>(&self, data: &[T], usage: BufferUsage) -> (Arc<DeviceLocalBuffer<[T]>>, impl GpuFuture) {
+		let buffer = self.device_local_buffer(data.len(), usage);
+		let staging = self.cpu_buffer_from_slice(data, BufferUsage::transfer_source());
+
+		let mut builder = self.transfer_command_buffer_builder();
+		builder.copy_buffer(staging, buffer.clone()).unwrap();
+		let future = builder.build().unwrap().execute(self.transfer_queue()).unwrap();
+		(buffer, future)
+	}
+
+	/// Copy the contents of `buffer` to the host, blocking until done.
+	/// GPU work writing to `buffer` must have finished (e.g. its fence waited on) before calling this.
+	pub fn download<T: Pod + Send + Sync>(&self, buffer: &Arc<DeviceLocalBuffer<[T]>>) -> Vec<T> {
+		let staging = self.cpu_buffer::<T>(buffer.len(), BufferUsage::transfer_destination());
+
+		let mut builder = self.transfer_command_buffer_builder();
+		builder.copy_buffer(buffer.clone(), staging.clone()).unwrap();
+		let finished = builder.build().unwrap().execute(self.transfer_queue()).unwrap();
+		finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+
+		let data = staging.read().unwrap().to_vec();
+		data
+	}
+}
+
+/// A slot of a `UniformRing`, bound to a kernel like any other buffer.
+pub type UniformBuffer<T> = CpuBufferPoolSubbuffer<T, Arc<StdMemoryPool>>;
+
+/// Ring of uniform buffers for per-dispatch parameters.
+///
+/// `T` is typically the struct vulkano-shaders generates for a uniform block, which has std140 layout.
+/// Slots are recycled once the GPU no longer uses them,
+/// so parameters can change between dispatches that are still in flight.
+pub struct UniformRing<T> {
+	pool: CpuBufferPool<T>,
+}
+
+impl<T: Send + Sync + 'static> UniformRing<T> {
+	/// Copy `params` into a free slot.
+	pub fn next(&self, params: T) -> UniformBuffer<T> {
+		self.pool.next(params).unwrap()
+	}
+}
+
+impl Interface {
+	pub fn uniform_ring<T>(&self) -> UniformRing<T> {
+		UniformRing {
+			pool: CpuBufferPool::uniform_buffer(self.device()),
+		}
+	}
+}
@@ -0,0 +1,36 @@
+#version 450
+
+// Per-pixel color space conversion. Alpha is copied.
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+#define GRAYSCALE 0
+#define SRGB_TO_LINEAR 1
+#define LINEAR_TO_SRGB 2
+
+// one of the above
+layout(constant_id = 3) const uint conversion = GRAYSCALE;
+
+layout(set = 0, binding = 0, rgba32f) uniform readonly image2D src;
+layout(set = 0, binding = 1, rgba32f) uniform writeonly image2D dst;
+
+void main() {
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(dst))))) {
+        return;
+    }
+
+    ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    vec4 color = imageLoad(src, pos);
+    vec3 c = color.rgb;
+    if (conversion == GRAYSCALE) {
+        // Rec. 709 luma
+        c = vec3(dot(c, vec3(0.2126, 0.7152, 0.0722)));
+    } else if (conversion == SRGB_TO_LINEAR) {
+        c = mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), greaterThan(c, vec3(0.04045)));
+    } else {
+        c = mix(c * 12.92, 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055, greaterThan(c, vec3(0.0031308)));
+    }
+
+    imageStore(dst, pos, vec4(c, color.a));
+}
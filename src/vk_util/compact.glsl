@@ -0,0 +1,44 @@
+#version 450
+
+// Stream compaction: copies the flagged elements to the front of the output, keeping their order.
+// Elements are copied as bits, so any 4 byte type works.
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+layout(set = 0, binding = 0) readonly buffer Input {
+    uint data[];
+};
+
+// 1 to keep the element, 0 to drop it, as written by flags.glsl
+layout(set = 0, binding = 1) readonly buffer Flags {
+    uint flags[];
+};
+
+// exclusive scan of the flags: the output index of each kept element
+layout(set = 0, binding = 2) readonly buffer Offsets {
+    uint offsets[];
+};
+
+// at least as long as the number of kept elements
+layout(set = 0, binding = 3) writeonly buffer Output {
+    uint result[];
+};
+
+layout(set = 0, binding = 4) writeonly buffer Count {
+    uint count;
+};
+
+void main() {
+    // a grid-stride loop, since the dispatch is capped at a number of workgroups every device takes
+    uint len = uint(data.length());
+    uint stride = gl_NumWorkGroups.x * gl_WorkGroupSize.x;
+    for (uint index = gl_GlobalInvocationID.x; index < len; index += stride) {
+        uint keep = flags[index];
+        if (keep != 0) {
+            result[offsets[index]] = data[index];
+        }
+        if (index == len - 1) {
+            count = offsets[index] + keep;
+        }
+    }
+}
@@ -0,0 +1,37 @@
+#version 450
+
+// One pass of a separable convolution: filters the rows, or the columns, of an image with a 1D kernel.
+// Pixels beyond the edges repeat the edge pixels.
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+layout(set = 0, binding = 0, rgba32f) uniform readonly image2D src;
+layout(set = 0, binding = 1, rgba32f) uniform writeonly image2D dst;
+
+// weights of the pixels at offsets -radius to radius
+layout(set = 0, binding = 2) readonly buffer Weights {
+    float weights[];
+};
+
+layout(push_constant) uniform PushConstants {
+    // (1, 0) to filter rows, (0, 1) for columns
+    ivec2 direction;
+} params;
+
+void main() {
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(dst))))) {
+        return;
+    }
+
+    ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    ivec2 last = imageSize(src) - 1;
+    int radius = weights.length() / 2;
+    vec4 sum = vec4(0.0);
+    for (int i = 0; i < weights.length(); i++) {
+        ivec2 p = clamp(pos + (i - radius) * params.direction, ivec2(0), last);
+        sum += weights[i] * imageLoad(src, p);
+    }
+
+    imageStore(dst, pos, sum);
+}
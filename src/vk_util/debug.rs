@@ -0,0 +1,139 @@
+use super::*;
+
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::sync::Mutex;
+use vulkano::instance::debug::{DebugCallback, Message, MessageSeverity, MessageType};
+use vulkano::instance::{layers_list, Instance, InstanceCreationError, InstanceExtensions};
+
+/// Khronos validation layer, enabled in debug mode when installed.
+pub const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation";
+
+/// Destination for validation layer messages.
+#[derive(Clone)]
+pub enum MessageSink {
+	Stderr,
+	/// Append messages to a shared `Vec`, e.g. to inspect them in tests.
+	Capture(Arc<Mutex<Vec<ValidationMessage>>>),
+	Callback(Arc<dyn Fn(&ValidationMessage) + Send + Sync>),
+}
+
+impl MessageSink {
+	fn send(&self, msg: &ValidationMessage) {
+		match self {
+			MessageSink::Stderr => eprintln!("{}", msg),
+			MessageSink::Capture(v) => v.lock().unwrap().push(msg.clone()),
+			MessageSink::Callback(f) => f(msg),
+		}
+	}
+}
+
+impl fmt::Debug for MessageSink {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			MessageSink::Stderr => write!(f, "Stderr"),
+			MessageSink::Capture(_) => write!(f, "Capture"),
+			MessageSink::Callback(_) => write!(f, "Callback"),
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+	Verbose,
+	Information,
+	Warning,
+	Error,
+}
+
+/// A message reported through the debug utils messenger.
+#[derive(Clone, Debug)]
+pub struct ValidationMessage {
+	pub severity: Severity,
+	pub layer: String,
+	pub description: String,
+}
+
+impl fmt::Display for ValidationMessage {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "vulkan {:?} [{}]: {}", self.severity, self.layer, self.description)
+	}
+}
+
+impl From<&Message<'_>> for ValidationMessage {
+	fn from(m: &Message) -> Self {
+		let severity = match m.severity {
+			s if s.error => Severity::Error,
+			s if s.warning => Severity::Warning,
+			s if s.information => Severity::Information,
+			_ => Severity::Verbose,
+		};
+		Self {
+			severity,
+			layer: m.layer_prefix.to_string(),
+			description: m.description.to_string(),
+		}
+	}
+}
+
+/// Keeps the debug messenger alive and records every error-severity message it received.
+pub(crate) struct DebugMessenger {
+	_callback: Option<DebugCallback>,
+	errors: Arc<Mutex<Vec<ValidationMessage>>>,
+	validation: bool,
+}
+
+impl DebugMessenger {
+	/// Create an instance with the validation layer and debug utils extension enabled, as far as they are available.
+	/// It records the workgroup size of compute shaders for `Kernel`, see `reflect`.
+	pub fn instance(debug: bool) -> Result<Arc<Instance>, InstanceCreationError> {
+		if !debug {
+			return Instance::with_loader(recording_loader()?, None, &InstanceExtensions::none(), None);
+		}
+		let extensions = InstanceExtensions {
+			ext_debug_utils: InstanceExtensions::supported_by_core()?.ext_debug_utils,
+			..InstanceExtensions::none()
+		};
+		let validation = match layers_list() {
+			Ok(mut layers) => layers.any(|l| l.name() == VALIDATION_LAYER),
+			Err(_) => false,
+		};
+		let layers = if validation { Some(VALIDATION_LAYER) } else { None };
+		Instance::with_loader(recording_loader()?, None, &extensions, layers)
+	}
+
+	/// Route warnings and errors to `sink`.
+	/// Without the debug utils extension, no messages are delivered.
+	pub fn attach(instance: &Arc<Instance>, sink: MessageSink) -> Self {
+		let errors = Arc::new(Mutex::new(Vec::new()));
+		let validation = instance.loaded_layers().any(|l| l.to_str() == Ok(VALIDATION_LAYER));
+
+		let record = errors.clone();
+		// Panics in the callback are caught and ignored by vulkano.
+		let sink = AssertUnwindSafe(sink);
+		let callback = DebugCallback::new(instance, MessageSeverity::errors_and_warnings(), MessageType::all(), move |m| {
+			let msg = ValidationMessage::from(m);
+			if msg.severity == Severity::Error {
+				record.lock().unwrap().push(msg.clone());
+			}
+			sink.send(&msg);
+		})
+		.ok();
+
+		Self {
+			_callback: callback,
+			errors,
+			validation,
+		}
+	}
+
+	/// Whether `VK_LAYER_KHRONOS_validation` is active.
+	pub fn validation(&self) -> bool {
+		self.validation
+	}
+
+	/// Error-severity messages received so far.
+	pub fn errors(&self) -> Vec<ValidationMessage> {
+		self.errors.lock().unwrap().clone()
+	}
+}
@@ -0,0 +1,95 @@
+#version 450
+
+// Reduces an image by an integer factor, for supersampling.
+//
+// Compiled with FORMAT defined as the images' format qualifier (default rgba8).
+// Single channel formats such as r32f load as (r, 0, 0, 1) and store only r.
+// With SRGB defined instead, the source is rgba16f holding linear intensities,
+// and the rgba8 destination receives them sRGB encoded, so that the filter averages in linear space.
+#ifdef SRGB
+#define FORMAT rgba16f
+#define DST_FORMAT rgba8
+#endif
+#ifndef FORMAT
+#define FORMAT rgba8
+#endif
+#ifndef DST_FORMAT
+#define DST_FORMAT FORMAT
+#endif
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+#define BOX 0
+#define LANCZOS3 1
+
+// one of the above
+layout(constant_id = 3) const uint filter_type = BOX;
+
+layout(set = 0, binding = 0, FORMAT) uniform readonly image2D src;
+layout(set = 0, binding = 1, DST_FORMAT) uniform writeonly image2D dst;
+
+layout(push_constant) uniform PushConstants {
+    // source size / destination size
+    uint factor;
+} params;
+
+const float PI = 3.14159265358979;
+
+vec4 encode(vec4 color) {
+#ifdef SRGB
+    vec3 c = clamp(color.rgb, 0.0, 1.0);
+    vec3 srgb = mix(c * 12.92, 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055, greaterThan(c, vec3(0.0031308)));
+    return vec4(srgb, color.a);
+#else
+    return color;
+#endif
+}
+
+float lanczos3(float x) {
+    if (x == 0.0) {
+        return 1.0;
+    }
+    if (abs(x) >= 3.0) {
+        return 0.0;
+    }
+    float px = PI * x;
+    return 3.0 * sin(px) * sin(px / 3.0) / (px * px);
+}
+
+void main() {
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(dst))))) {
+        return;
+    }
+
+    ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    int n = int(params.factor);
+    vec4 sum = vec4(0.0);
+
+    if (filter_type == BOX) {
+        for (int y = 0; y < n; y++) {
+            for (int x = 0; x < n; x++) {
+                sum += imageLoad(src, pos * n + ivec2(x, y));
+            }
+        }
+        sum /= float(n * n);
+    } else {
+        // The filter extends 3 destination pixels to either side of the pixel center.
+        // Taps outside the source are left out, and the weights renormalized.
+        vec2 center = (vec2(pos) + 0.5) * float(n);
+        ivec2 lo = max(ivec2(floor(center - 3.0 * float(n))), ivec2(0));
+        ivec2 hi = min(ivec2(ceil(center + 3.0 * float(n))), imageSize(src));
+        float total = 0.0;
+        for (int y = lo.y; y < hi.y; y++) {
+            float wy = lanczos3((float(y) + 0.5 - center.y) / float(n));
+            for (int x = lo.x; x < hi.x; x++) {
+                float w = wy * lanczos3((float(x) + 0.5 - center.x) / float(n));
+                sum += w * imageLoad(src, ivec2(x, y));
+                total += w;
+            }
+        }
+        sum /= total;
+    }
+
+    imageStore(dst, pos, encode(sum));
+}
@@ -0,0 +1,168 @@
+use super::*;
+
+use vulkano::image::ImageAccess;
+
+mod cs_rgba8 {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/downsample.glsl",
+	}
+}
+
+mod cs_rgba16f {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/downsample.glsl",
+		define: [("FORMAT", "rgba16f")],
+	}
+}
+
+mod cs_rgba32f {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/downsample.glsl",
+		define: [("FORMAT", "rgba32f")],
+	}
+}
+
+mod cs_srgb {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/downsample.glsl",
+		define: [("SRGB", "1")],
+	}
+}
+
+mod cs_r32f {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/downsample.glsl",
+		define: [("FORMAT", "r32f")],
+	}
+}
+
+/// Reconstruction filter of a `Downsample`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DownsampleFilter {
+	/// Average of the source pixels covering each destination pixel.
+	Box,
+	/// Windowed sinc over 3 destination pixels on either side. Sharper than `Box`, but rings near hard edges.
+	Lanczos3,
+}
+
+/// Reduces images by an integer factor on the GPU, e.g. to resolve a supersampled render:
+///
+/// ```ignore
+/// let large = vk.storage_image((4 * w, 4 * h), Format::R8G8B8A8Unorm);
+/// let small = vk.storage_image((w, h), Format::R8G8B8A8Unorm);
+/// // ... render into large ...
+/// Downsample::new(&vk, DownsampleFilter::Box).record(&mut builder, large, small);
+/// ```
+///
+/// Supports `R8G8B8A8Unorm`, `R16G16B16A16Sfloat`, `R32G32B32A32Sfloat` and `R32Sfloat` images.
+/// `R8G8B8A8Unorm` averages the stored values, which for sRGB encoded colors darkens edges: to filter in
+/// linear space, downsample linear intensities in `R16G16B16A16Sfloat` into `R8G8B8A8Unorm`, which encodes them as sRGB.
+pub struct Downsample {
+	rgba8: Kernel<cs_rgba8::ty::PushConstants>,
+	rgba16f: Kernel<cs_rgba16f::ty::PushConstants>,
+	rgba32f: Kernel<cs_rgba32f::ty::PushConstants>,
+	srgb: Kernel<cs_srgb::ty::PushConstants>,
+	r32f: Kernel<cs_r32f::ty::PushConstants>,
+}
+
+impl Downsample {
+	pub fn new(vk: &Interface, filter: DownsampleFilter) -> Self {
+		let filter_type = match filter {
+			DownsampleFilter::Box => 0,
+			DownsampleFilter::Lanczos3 => 1,
+		};
+		let rgba8 = cs_rgba8::Shader::load(vk.device()).unwrap();
+		let rgba16f = cs_rgba16f::Shader::load(vk.device()).unwrap();
+		let rgba32f = cs_rgba32f::Shader::load(vk.device()).unwrap();
+		let srgb = cs_srgb::Shader::load(vk.device()).unwrap();
+		let r32f = cs_r32f::Shader::load(vk.device()).unwrap();
+		Self {
+			rgba8: Kernel::with_specialization(
+				vk,
+				&rgba8.main_entry_point(),
+				cs_rgba8::SpecializationConstants {
+					filter_type,
+					..Default::default()
+				},
+			),
+			rgba16f: Kernel::with_specialization(
+				vk,
+				&rgba16f.main_entry_point(),
+				cs_rgba16f::SpecializationConstants {
+					filter_type,
+					..Default::default()
+				},
+			),
+			rgba32f: Kernel::with_specialization(
+				vk,
+				&rgba32f.main_entry_point(),
+				cs_rgba32f::SpecializationConstants {
+					filter_type,
+					..Default::default()
+				},
+			),
+			srgb: Kernel::with_specialization(
+				vk,
+				&srgb.main_entry_point(),
+				cs_srgb::SpecializationConstants {
+					filter_type,
+					..Default::default()
+				},
+			),
+			r32f: Kernel::with_specialization(
+				vk,
+				&r32f.main_entry_point(),
+				cs_r32f::SpecializationConstants {
+					filter_type,
+					..Default::default()
+				},
+			),
+		}
+	}
+
+	/// Record a dispatch that filters `src` into `dst`.
+	///
+	/// Panics unless both images have the same, supported format, or are `R16G16B16A16Sfloat` and `R8G8B8A8Unorm`,
+	/// and `src` is `dst` scaled up by an integer factor in both directions.
+	pub fn record(&self, builder: &mut AutoCommandBufferBuilder, src: Arc<StorageImage<Format>>, dst: Arc<StorageImage<Format>>) {
+		let [sw, sh] = src.dimensions().width_height();
+		let [dw, dh] = dst.dimensions().width_height();
+		let factor = sw / dw;
+		assert!(
+			factor >= 1 && sw == factor * dw && sh == factor * dh,
+			"downsample: {}x{} is not an integer multiple of {}x{}",
+			sw,
+			sh,
+			dw,
+			dh
+		);
+		let size = [dw, dh, 1];
+		match (src.format(), dst.format()) {
+			(Format::R8G8B8A8Unorm, Format::R8G8B8A8Unorm) => {
+				self.rgba8
+					.record_dispatch(builder, size, (src, dst), cs_rgba8::ty::PushConstants { factor })
+			}
+			(Format::R16G16B16A16Sfloat, Format::R16G16B16A16Sfloat) => {
+				self.rgba16f
+					.record_dispatch(builder, size, (src, dst), cs_rgba16f::ty::PushConstants { factor })
+			}
+			(Format::R32G32B32A32Sfloat, Format::R32G32B32A32Sfloat) => {
+				self.rgba32f
+					.record_dispatch(builder, size, (src, dst), cs_rgba32f::ty::PushConstants { factor })
+			}
+			(Format::R16G16B16A16Sfloat, Format::R8G8B8A8Unorm) => {
+				self.srgb
+					.record_dispatch(builder, size, (src, dst), cs_srgb::ty::PushConstants { factor })
+			}
+			(Format::R32Sfloat, Format::R32Sfloat) => self
+				.r32f
+				.record_dispatch(builder, size, (src, dst), cs_r32f::ty::PushConstants { factor }),
+			(s, d) => panic!("downsample: unsupported formats {:?} to {:?}", s, d),
+		}
+	}
+}
@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use tiff::TiffError;
+use vulkano::device::DeviceCreationError;
+use vulkano::instance::InstanceCreationError;
+
+use super::{DeviceSelection, ExportLayout, FileFormat};
+
+/// Error returned when an `Interface` cannot be constructed.
+#[derive(Debug)]
+pub enum InterfaceError {
+	/// The Vulkan instance could not be created (e.g. no Vulkan loader installed).
+	Instance(InstanceCreationError),
+	/// The instance does not expose any physical device.
+	NoPhysicalDevice,
+	/// No physical device matches the selection policy.
+	DeviceNotFound(DeviceSelection),
+	/// The selected physical device has no suitable queue family.
+	NoQueueFamily(String),
+	/// The physical device lacks required features or extensions.
+	MissingRequirements { device: String, missing: Vec<String> },
+	/// The logical device could not be created.
+	Device(DeviceCreationError),
+}
+
+impl fmt::Display for InterfaceError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use InterfaceError::*;
+		match self {
+			Instance(e) => write!(f, "create vulkan instance: {}", e),
+			NoPhysicalDevice => write!(f, "no vulkan device available"),
+			DeviceNotFound(sel) => write!(f, "no vulkan device matches selection: {}", sel),
+			NoQueueFamily(device) => write!(f, "no suitable queue family on {}", device),
+			MissingRequirements { device, missing } => write!(f, "{} lacks required {}", device, missing.join(", ")),
+			Device(e) => write!(f, "create vulkan device: {}", e),
+		}
+	}
+}
+
+impl Error for InterfaceError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		use InterfaceError::*;
+		match self {
+			Instance(e) => Some(e),
+			Device(e) => Some(e),
+			NoPhysicalDevice | DeviceNotFound(_) | NoQueueFamily(_) | MissingRequirements { .. } => None,
+		}
+	}
+}
+
+impl From<InstanceCreationError> for InterfaceError {
+	fn from(e: InstanceCreationError) -> Self {
+		InterfaceError::Instance(e)
+	}
+}
+
+impl From<DeviceCreationError> for InterfaceError {
+	fn from(e: DeviceCreationError) -> Self {
+		InterfaceError::Device(e)
+	}
+}
+
+/// Error returned by an `Export`.
+#[derive(Debug)]
+pub enum ExportError {
+	/// The file extension does not name a supported format.
+	UnknownFormat(PathBuf),
+	/// The file format cannot store images with this layout (e.g. float samples in a PNG).
+	Unsupported(FileFormat, ExportLayout),
+	/// Rows were written with a length that is not a multiple of the row size.
+	PartialRow { len: usize, row_size: usize },
+	/// More rows than the image height were written, or fewer before finishing.
+	RowCount { written: u32, height: u32 },
+	/// Writing the file failed.
+	Io(io::Error),
+	/// The TIFF encoder failed.
+	Tiff(TiffError),
+	/// An encoder thread panicked.
+	Panicked,
+}
+
+impl fmt::Display for ExportError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use ExportError::*;
+		match self {
+			UnknownFormat(path) => write!(f, "{}: unknown image format", path.display()),
+			Unsupported(format, layout) => write!(
+				f,
+				"{:?} does not support {} channel(s) of {:?} samples",
+				format, layout.channels, layout.sample
+			),
+			PartialRow { len, row_size } => write!(f, "{} bytes is not a whole number of {} byte rows", len, row_size),
+			RowCount { written, height } => write!(f, "{} rows written to an image of height {}", written, height),
+			Io(e) => write!(f, "write image: {}", e),
+			Tiff(e) => write!(f, "encode tiff: {}", e),
+			Panicked => write!(f, "image encoder panicked"),
+		}
+	}
+}
+
+impl Error for ExportError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		use ExportError::*;
+		match self {
+			Io(e) => Some(e),
+			Tiff(e) => Some(e),
+			UnknownFormat(_) | Unsupported(..) | PartialRow { .. } | RowCount { .. } | Panicked => None,
+		}
+	}
+}
+
+impl From<io::Error> for ExportError {
+	fn from(e: io::Error) -> Self {
+		ExportError::Io(e)
+	}
+}
+
+impl From<TiffError> for ExportError {
+	fn from(e: TiffError) -> Self {
+		ExportError::Tiff(e)
+	}
+}
@@ -0,0 +1,721 @@
+use super::ExportError;
+
+use adler32::RollingAdler32;
+use deflate::write::DeflateEncoder;
+use deflate::Compression;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tiff::encoder::colortype::{self, ColorType};
+use tiff::encoder::{TiffEncoder, TiffValue};
+use tiff::tags::{PhotometricInterpretation, Tag};
+use vulkano::format::Format;
+
+/// Image file format of an `Export`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+	/// 8 or 16 bit integer samples, deflate-compressed.
+	Png,
+	/// Any samples, uncompressed.
+	Tiff,
+	/// OpenEXR with 16 or 32 bit float samples, uncompressed scanlines.
+	Exr,
+	/// Binary PPM (or PGM for a single channel) with 8 or 16 bit samples. Has no alpha channel.
+	Ppm,
+}
+
+impl FileFormat {
+	/// The format for a file extension like "png" or "tif".
+	pub fn from_extension(ext: &str) -> Option<Self> {
+		use FileFormat::*;
+		match ext.to_lowercase().as_str() {
+			"png" => Some(Png),
+			"tif" | "tiff" => Some(Tiff),
+			"exr" => Some(Exr),
+			"ppm" | "pgm" | "pnm" => Some(Ppm),
+			_ => None,
+		}
+	}
+
+	/// The format for a path's extension.
+	pub fn from_path(path: &Path) -> Option<Self> {
+		Self::from_extension(path.extension()?.to_str()?)
+	}
+
+	/// Whether this format can store images with `layout`.
+	pub fn supports(self, layout: ExportLayout) -> bool {
+		use FileFormat::*;
+		use SampleType::*;
+		let channels_ok = layout.channels == 1 || layout.channels == 4;
+		let sample_ok = match self {
+			Png | Ppm => layout.sample == U8 || layout.sample == U16,
+			Exr => layout.sample == F16 || layout.sample == F32,
+			Tiff => true,
+		};
+		channels_ok && sample_ok && layout.width > 0 && layout.height > 0
+	}
+}
+
+/// Type of each channel of a pixel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SampleType {
+	U8,
+	U16,
+	/// Half precision float.
+	F16,
+	F32,
+}
+
+impl SampleType {
+	/// Size in bytes.
+	pub fn size(self) -> usize {
+		match self {
+			SampleType::U8 => 1,
+			SampleType::U16 | SampleType::F16 => 2,
+			SampleType::F32 => 4,
+		}
+	}
+}
+
+/// Size and pixel layout of an exported image.
+///
+/// Pixels have 1 (gray) or 4 (RGBA) channels, stored row by row in native byte order,
+/// the way images are read back from the GPU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ExportLayout {
+	pub width: u32,
+	pub height: u32,
+	pub channels: u32,
+	pub sample: SampleType,
+}
+
+impl ExportLayout {
+	pub fn new(width: u32, height: u32, channels: u32, sample: SampleType) -> Self {
+		Self {
+			width,
+			height,
+			channels,
+			sample,
+		}
+	}
+
+	/// The layout of a `width` x `height` image of `format`, as copied to a buffer.
+	/// `None` for formats other than 8 or 16 bit unsigned normalized, and 16 or 32 bit float,
+	/// with one channel or RGBA.
+	pub fn for_format(format: Format, width: u32, height: u32) -> Option<Self> {
+		use Format::*;
+		use SampleType::*;
+		let (channels, sample) = match format {
+			R8Unorm => (1, U8),
+			R8G8B8A8Unorm => (4, U8),
+			R16Unorm => (1, U16),
+			R16G16B16A16Unorm => (4, U16),
+			R16Sfloat => (1, F16),
+			R16G16B16A16Sfloat => (4, F16),
+			R32Sfloat => (1, F32),
+			R32G32B32A32Sfloat => (4, F32),
+			_ => return None,
+		};
+		Some(Self::new(width, height, channels, sample))
+	}
+
+	/// Size of a pixel in bytes.
+	pub fn pixel_size(&self) -> usize {
+		self.channels as usize * self.sample.size()
+	}
+
+	/// Size of a row in bytes.
+	pub fn row_size(&self) -> usize {
+		self.width as usize * self.pixel_size()
+	}
+}
+
+/// Image file written band by band, e.g. as the tiles of an image are read back from the GPU:
+///
+/// ```ignore
+/// let mut export = Export::create("image.png", ExportLayout::new(w, h, 4, SampleType::U8))?;
+/// for band in bands {
+///     // ... render and read back the next rows ...
+///     export.write_rows(&rows)?;
+/// }
+/// export.finish()?;
+/// ```
+///
+/// `write_rows` hands the rows to a pool of worker threads and returns right away,
+/// so that encoding overlaps with rendering the next band.
+/// PNG bands are filtered and compressed in parallel; a writer thread puts the results in order.
+/// When the workers fall behind, `write_rows` blocks, so at most a few bands are in memory.
+pub struct Export {
+	layout: ExportLayout,
+	rows_written: u32,
+	bands_written: usize,
+	// last row written, PNG filters refer to it
+	last_row: Vec<u8>,
+	bands: Option<SyncSender<Band>>,
+	workers: Vec<JoinHandle<()>>,
+	writer: Option<JoinHandle<Result<(), ExportError>>>,
+}
+
+impl Export {
+	/// Create the file at `path`, in the format given by its extension.
+	pub fn create(path: impl AsRef<Path>, layout: ExportLayout) -> Result<Self, ExportError> {
+		let path = path.as_ref();
+		let format = FileFormat::from_path(path).ok_or_else(|| ExportError::UnknownFormat(path.to_owned()))?;
+		Self::with_format(path, format, layout)
+	}
+
+	/// Create the file at `path`, in `format` regardless of its extension.
+	pub fn with_format(path: impl AsRef<Path>, format: FileFormat, layout: ExportLayout) -> Result<Self, ExportError> {
+		use SampleType::*;
+		if !format.supports(layout) {
+			return Err(ExportError::Unsupported(format, layout));
+		}
+		let file = BufWriter::new(File::create(path)?);
+		let (bands, workers, writer) = match format {
+			FileFormat::Png => spawn(move |band| deflate_band(band, layout), move |bands| write_png(file, layout, bands)),
+			FileFormat::Tiff => spawn(
+				|band| band.rows,
+				move |bands| match (layout.channels, layout.sample) {
+					(1, U8) => write_tiff::<colortype::Gray8>(file, layout, bands),
+					(1, U16) | (1, F16) => write_tiff::<colortype::Gray16>(file, layout, bands),
+					(1, F32) => write_tiff::<colortype::Gray32>(file, layout, bands),
+					(_, U8) => write_tiff::<colortype::RGBA8>(file, layout, bands),
+					(_, U16) | (_, F16) => write_tiff::<colortype::RGBA16>(file, layout, bands),
+					(_, F32) => write_tiff::<Rgba32>(file, layout, bands),
+				},
+			),
+			FileFormat::Exr => spawn(move |band| exr_blocks(band, layout), move |bands| write_exr(file, layout, bands)),
+			FileFormat::Ppm => spawn(move |band| ppm_samples(band, layout), move |bands| write_ppm(file, layout, bands)),
+		};
+		Ok(Self {
+			layout,
+			rows_written: 0,
+			bands_written: 0,
+			last_row: Vec::new(),
+			bands: Some(bands),
+			workers,
+			writer: Some(writer),
+		})
+	}
+
+	pub fn layout(&self) -> ExportLayout {
+		self.layout
+	}
+
+	/// Queue the next whole rows, in the layout's pixel format, for encoding.
+	pub fn write_rows(&mut self, rows: &[u8]) -> Result<(), ExportError> {
+		let row_size = self.layout.row_size();
+		let count = rows.len() / row_size;
+		if count * row_size != rows.len() {
+			return Err(ExportError::PartialRow { len: rows.len(), row_size });
+		}
+		let count = count as u32;
+		let written = self.rows_written + count;
+		if written > self.layout.height {
+			return Err(ExportError::RowCount {
+				written,
+				height: self.layout.height,
+			});
+		}
+		if count == 0 {
+			return Ok(());
+		}
+
+		let band = Band {
+			index: self.bands_written,
+			y: self.rows_written,
+			above: std::mem::replace(&mut self.last_row, rows[rows.len() - row_size..].to_vec()),
+			rows: rows.to_vec(),
+		};
+		self.rows_written = written;
+		self.bands_written += 1;
+		let sent = self.bands.as_ref().map(|bands| bands.send(band).is_ok());
+		if sent != Some(true) {
+			// the writer stopped early, its result has the reason
+			return Err(self.join().err().unwrap_or(ExportError::Panicked));
+		}
+		Ok(())
+	}
+
+	/// Wait for all rows to be encoded and complete the file.
+	/// All rows of the image must have been written.
+	pub fn finish(mut self) -> Result<(), ExportError> {
+		if self.rows_written != self.layout.height {
+			return Err(ExportError::RowCount {
+				written: self.rows_written,
+				height: self.layout.height,
+			});
+		}
+		self.join()
+	}
+
+	// Close the band queue and wait for the threads to exit.
+	fn join(&mut self) -> Result<(), ExportError> {
+		self.bands = None;
+		let workers: Vec<_> = self.workers.drain(..).map(JoinHandle::join).collect();
+		let workers_ok = workers.iter().all(Result::is_ok);
+		let result = match self.writer.take() {
+			Some(writer) => writer.join().unwrap_or(Err(ExportError::Panicked)),
+			None => Err(ExportError::Panicked),
+		};
+		match result {
+			Ok(()) if !workers_ok => Err(ExportError::Panicked),
+			result => result,
+		}
+	}
+}
+
+// Consecutive rows of an image, queued for encoding.
+struct Band {
+	// position in the queue
+	index: usize,
+	// first row
+	y: u32,
+	// row above the first, empty for the top of the image
+	above: Vec<u8>,
+	rows: Vec<u8>,
+}
+
+// Start worker threads that apply `encode` to each band sent to the returned queue,
+// and a writer thread that passes the results, in order, to `write`.
+#[allow(clippy::type_complexity)]
+fn spawn<T, E, W>(encode: E, write: W) -> (SyncSender<Band>, Vec<JoinHandle<()>>, JoinHandle<Result<(), ExportError>>)
+where
+	T: Send + 'static,
+	E: Fn(Band) -> T + Send + Sync + 'static,
+	W: FnOnce(InOrder<T>) -> Result<(), ExportError> + Send + 'static,
+{
+	let n = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+	// bounded queues limit the bands in memory to about 3n
+	let (bands, band_queue) = mpsc::sync_channel::<Band>(n);
+	let (results, result_queue) = mpsc::sync_channel(n);
+	let band_queue = Arc::new(Mutex::new(band_queue));
+	let encode = Arc::new(encode);
+
+	let workers = (0..n)
+		.map(|_| {
+			let band_queue = band_queue.clone();
+			let results = results.clone();
+			let encode = encode.clone();
+			thread::spawn(move || loop {
+				let band = match band_queue.lock().unwrap().recv() {
+					Ok(band) => band,
+					Err(_) => return, // queue closed
+				};
+				let index = band.index;
+				if results.send((index, encode(band))).is_err() {
+					return; // writer stopped
+				}
+			})
+		})
+		.collect();
+
+	let writer = thread::spawn(move || {
+		write(InOrder {
+			results: result_queue,
+			pending: BTreeMap::new(),
+			next: 0,
+		})
+	});
+	(bands, workers, writer)
+}
+
+// Iterates over encoded bands in image order, as they arrive from the workers in any order.
+struct InOrder<T> {
+	results: Receiver<(usize, T)>,
+	pending: BTreeMap<usize, T>,
+	next: usize,
+}
+
+impl<T> Iterator for InOrder<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		loop {
+			if let Some(result) = self.pending.remove(&self.next) {
+				self.next += 1;
+				return Some(result);
+			}
+			let (index, result) = self.results.recv().ok()?;
+			self.pending.insert(index, result);
+		}
+	}
+}
+
+// Convert 16 bit samples from native to big endian byte order, in place.
+fn to_big_endian(mut data: Vec<u8>, sample: SampleType) -> Vec<u8> {
+	if sample == SampleType::U16 {
+		for s in data.chunks_exact_mut(2) {
+			let v = u16::from_ne_bytes([s[0], s[1]]);
+			s.copy_from_slice(&v.to_be_bytes());
+		}
+	}
+	data
+}
+
+// PNG
+//
+// The pixel data of a PNG is a single zlib stream (RFC 1950) over all filtered rows,
+// split over IDAT chunks at arbitrary points. Bands are compressed on separate threads
+// and their outputs stitched together into that one stream, which is valid because:
+//
+// - The stream starts with the zlib header 0x78 0x9c: deflate with a 32K window,
+//   default compression level, no preset dictionary, and a check value making it a multiple of 31.
+// - Each band is a sequence of raw deflate blocks (RFC 1951) ending in a sync flush, the empty stored block
+//   00 00 ff ff, which ends on a byte boundary. None of the blocks is marked final, so the bands
+//   concatenate into one valid sequence of blocks. Each band's compressor starts with an empty window,
+//   so back references stay within the band; this costs a little compression and nothing else.
+// - The final block is empty, with fixed Huffman codes: the bits BFINAL = 1 and BTYPE = 01,
+//   then the 7 bit end-of-block code 0, padded to whole bytes, which is 0x03 0x00.
+// - The Adler-32 checksum of all uncompressed data ends the stream. Each worker only sees its own band,
+//   so the writer combines the bands' checksums in order, the way zlib's adler32_combine does.
+//
+// The png_zlib_stream test inflates the result with a separate decoder that verifies the checksum.
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+// Largest IDAT chunk written.
+const MAX_CHUNK: usize = 1 << 20;
+
+// Part of the zlib stream in a PNG.
+struct Deflated {
+	// deflate blocks, ending in a sync flush
+	data: Vec<u8>,
+	// adler32 checksum and length of the uncompressed data
+	adler: u32,
+	len: u64,
+}
+
+// Filter and compress a band.
+// Bands are compressed independently, with a sync flush at the end so that they can be concatenated.
+fn deflate_band(band: Band, layout: ExportLayout) -> Deflated {
+	let row_size = layout.row_size();
+	let above = match band.above.len() {
+		0 => vec![0; row_size],
+		_ => to_big_endian(band.above, layout.sample),
+	};
+	let rows = to_big_endian(band.rows, layout.sample);
+
+	let mut filtered = Vec::with_capacity(rows.len() + rows.len() / row_size);
+	let mut candidates = vec![vec![0; row_size]; 5];
+	let mut above = &above[..];
+	for row in rows.chunks_exact(row_size) {
+		filter_row(row, above, layout.pixel_size(), &mut candidates, &mut filtered);
+		above = row;
+	}
+
+	let mut data = Vec::new();
+	let flushed = Cell::new(false);
+	let mut encoder = DeflateEncoder::new(
+		UntilFlushed {
+			out: &mut data,
+			flushed: &flushed,
+		},
+		Compression::Default,
+	);
+	// writing to memory does not fail
+	encoder.write_all(&filtered).unwrap();
+	encoder.flush().unwrap();
+	// the encoder writes a final block when dropped, which would end the stream
+	flushed.set(true);
+	drop(encoder);
+
+	Deflated {
+		data,
+		adler: RollingAdler32::from_buffer(&filtered).hash(),
+		len: filtered.len() as u64,
+	}
+}
+
+// Passes writes to `out` until `flushed` is set, then discards them.
+struct UntilFlushed<'a> {
+	out: &'a mut Vec<u8>,
+	flushed: &'a Cell<bool>,
+}
+
+impl<'a> Write for UntilFlushed<'a> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		if !self.flushed.get() {
+			self.out.extend_from_slice(buf);
+		}
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+// Append the filter type and filtered `row` to `out`, choosing the filter with the smallest sum of absolute values
+// (as recommended by the PNG specification). `candidates` holds 5 rows of scratch space.
+fn filter_row(row: &[u8], above: &[u8], pixel_size: usize, candidates: &mut [Vec<u8>], out: &mut Vec<u8>) {
+	for i in 0..row.len() {
+		let (x, b) = (row[i], above[i]);
+		let (a, c) = if i >= pixel_size {
+			(row[i - pixel_size], above[i - pixel_size])
+		} else {
+			(0, 0)
+		};
+		candidates[0][i] = x;
+		candidates[1][i] = x.wrapping_sub(a);
+		candidates[2][i] = x.wrapping_sub(b);
+		candidates[3][i] = x.wrapping_sub(((a as u16 + b as u16) / 2) as u8);
+		candidates[4][i] = x.wrapping_sub(paeth(a, b, c));
+	}
+	let cost = |filtered: &Vec<u8>| filtered.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum::<u64>();
+	let best = (0..candidates.len()).min_by_key(|&f| cost(&candidates[f])).unwrap();
+	out.push(best as u8);
+	out.extend_from_slice(&candidates[best]);
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+	let p = a as i16 + b as i16 - c as i16;
+	let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+	if pa <= pb && pa <= pc {
+		a
+	} else if pb <= pc {
+		b
+	} else {
+		c
+	}
+}
+
+// Adler32 checksum of the concatenation of data with checksum `adler1`
+// and `len2` bytes of data with checksum `adler2` (as zlib's adler32_combine).
+fn adler32_combine(adler1: u32, adler2: u32, len2: u64) -> u32 {
+	const BASE: u64 = 65521;
+	let (a1, b1) = (adler1 as u64 & 0xffff, adler1 as u64 >> 16);
+	let (a2, b2) = (adler2 as u64 & 0xffff, adler2 as u64 >> 16);
+	let rem = len2 % BASE;
+	let a = (a1 + a2 + BASE - 1) % BASE;
+	let b = (rem * a1 + b1 + b2 + BASE - rem) % BASE;
+	(a | (b << 16)) as u32
+}
+
+fn write_png(mut file: BufWriter<File>, layout: ExportLayout, bands: InOrder<Deflated>) -> Result<(), ExportError> {
+	let mut header = Vec::with_capacity(13);
+	header.extend_from_slice(&layout.width.to_be_bytes());
+	header.extend_from_slice(&layout.height.to_be_bytes());
+	let depth = 8 * layout.sample.size() as u8;
+	let color_type = match layout.channels {
+		1 => 0, // grayscale
+		_ => 6, // RGBA
+	};
+	// no compression, filter or interlace options
+	header.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+
+	file.write_all(PNG_SIGNATURE)?;
+	write_chunk(&mut file, b"IHDR", &header)?;
+	// zlib header: deflate, 32K window, default compression
+	write_chunk(&mut file, b"IDAT", &[0x78, 0x9c])?;
+	let mut adler = 1;
+	for band in bands {
+		adler = adler32_combine(adler, band.adler, band.len);
+		for chunk in band.data.chunks(MAX_CHUNK) {
+			write_chunk(&mut file, b"IDAT", chunk)?;
+		}
+	}
+	// an empty final block ends the deflate stream, the checksum ends the zlib stream
+	let mut end = vec![0x03, 0x00];
+	end.extend_from_slice(&adler.to_be_bytes());
+	write_chunk(&mut file, b"IDAT", &end)?;
+	write_chunk(&mut file, b"IEND", &[])?;
+	file.flush()?;
+	Ok(())
+}
+
+fn write_chunk(w: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+	let mut crc = crc32fast::Hasher::new();
+	crc.update(kind);
+	crc.update(data);
+	w.write_all(&(data.len() as u32).to_be_bytes())?;
+	w.write_all(kind)?;
+	w.write_all(data)?;
+	w.write_all(&crc.finalize().to_be_bytes())
+}
+
+// TIFF
+
+// The SampleFormat tag, which the tiff crate does not name, and its value for IEEE floating point samples.
+const SAMPLE_FORMAT: Tag = Tag::Unknown(339);
+const SAMPLE_FORMAT_IEEE_FP: u16 = 3;
+
+// RGBA with 32 bit samples. The encoder's own `RGBA32` has 16 bit samples.
+struct Rgba32;
+
+impl ColorType for Rgba32 {
+	type Inner = u32;
+	const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::RGB;
+	const BITS_PER_SAMPLE: &'static [u16] = &[32, 32, 32, 32];
+}
+
+// Write the bands as strips of a TIFF with color type `C`.
+fn write_tiff<C>(file: BufWriter<File>, layout: ExportLayout, bands: InOrder<Vec<u8>>) -> Result<(), ExportError>
+where
+	C: ColorType,
+	C::Inner: bytemuck::Pod,
+	[C::Inner]: TiffValue,
+{
+	let mut tiff = TiffEncoder::new(file)?;
+	let mut image = tiff.new_image::<C>(layout.width, layout.height)?;
+	let channels = layout.channels as usize;
+	if channels == 4 {
+		// unassociated alpha
+		image.encoder().write_tag(Tag::ExtraSamples, 2u16)?;
+	}
+	// The encoder's color types only set the bits per sample, for unsigned integers.
+	// Float samples go through those of the same size, and SampleFormat marks them as floats:
+	// 16 bit floats are half precision, as in the TIFF extension for floating point data (Adobe technical note 3).
+	if layout.sample == SampleType::F16 || layout.sample == SampleType::F32 {
+		image.encoder().write_tag(SAMPLE_FORMAT, &vec![SAMPLE_FORMAT_IEEE_FP; channels][..])?;
+	}
+
+	// Strips have a fixed number of rows, unrelated to the bands.
+	let mut samples: Vec<C::Inner> = Vec::new();
+	for band in bands {
+		samples.extend(band.chunks_exact(layout.sample.size()).map(|bytes| {
+			let mut sample = <C::Inner as bytemuck::Zeroable>::zeroed();
+			bytemuck::bytes_of_mut(&mut sample).copy_from_slice(bytes);
+			sample
+		}));
+		let mut start = 0;
+		while image.next_strip_sample_count() > 0 && samples.len() - start >= image.next_strip_sample_count() as usize {
+			let end = start + image.next_strip_sample_count() as usize;
+			image.write_strip(&samples[start..end])?;
+			start = end;
+		}
+		samples.drain(..start);
+	}
+	image.finish()?;
+	Ok(())
+}
+
+// OpenEXR
+
+// Channel names, in the alphabetical order of the file, and their index in our pixels.
+fn exr_channels(layout: ExportLayout) -> &'static [(&'static str, usize)] {
+	match layout.channels {
+		1 => &[("Y", 0)],
+		_ => &[("A", 3), ("B", 2), ("G", 1), ("R", 0)],
+	}
+}
+
+// Size of a scanline block: y coordinate, data size and the pixel data.
+fn exr_block_size(layout: ExportLayout) -> usize {
+	8 + layout.row_size()
+}
+
+// Convert a band to uncompressed scanline blocks: each channel in turn, as little endian samples.
+fn exr_blocks(band: Band, layout: ExportLayout) -> Vec<u8> {
+	let row_size = layout.row_size();
+	let mut blocks = Vec::with_capacity(band.rows.len() / row_size * exr_block_size(layout));
+	for (i, row) in band.rows.chunks_exact(row_size).enumerate() {
+		blocks.extend_from_slice(&(band.y as i32 + i as i32).to_le_bytes());
+		blocks.extend_from_slice(&(row_size as i32).to_le_bytes());
+		let size = layout.sample.size();
+		for &(_, c) in exr_channels(layout) {
+			for pixel in row.chunks_exact(layout.pixel_size()) {
+				let sample = &pixel[c * size..(c + 1) * size];
+				if cfg!(target_endian = "little") {
+					blocks.extend_from_slice(sample);
+				} else {
+					blocks.extend(sample.iter().rev());
+				}
+			}
+		}
+	}
+	blocks
+}
+
+fn write_exr(mut file: BufWriter<File>, layout: ExportLayout, bands: InOrder<Vec<u8>>) -> Result<(), ExportError> {
+	let mut header = Vec::new();
+	// magic number, version 2, single part scanline file
+	header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+	let pixel_type = match layout.sample {
+		SampleType::F16 => 1, // HALF
+		_ => 2,               // FLOAT
+	};
+	let mut channels = Vec::new();
+	for (name, _) in exr_channels(layout) {
+		channels.extend_from_slice(name.as_bytes());
+		// NUL, pixel type, pLinear and reserved, x and y sampling 1
+		channels.extend_from_slice(&[0, pixel_type, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+	}
+	channels.push(0);
+	let mut window = Vec::new();
+	for v in &[0, 0, layout.width as i32 - 1, layout.height as i32 - 1] {
+		window.extend_from_slice(&i32::to_le_bytes(*v));
+	}
+	let attributes: &[(&str, &str, &[u8])] = &[
+		("channels", "chlist", &channels),
+		("compression", "compression", &[0]),
+		("dataWindow", "box2i", &window),
+		("displayWindow", "box2i", &window),
+		("lineOrder", "lineOrder", &[0]),
+		("pixelAspectRatio", "float", &1f32.to_le_bytes()),
+		("screenWindowCenter", "v2f", &[0; 8]),
+		("screenWindowWidth", "float", &1f32.to_le_bytes()),
+	];
+	for (name, ty, value) in attributes {
+		header.extend_from_slice(name.as_bytes());
+		header.push(0);
+		header.extend_from_slice(ty.as_bytes());
+		header.push(0);
+		header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+		header.extend_from_slice(value);
+	}
+	header.push(0);
+
+	// Blocks have a fixed size, so the offset table can be written up front.
+	let data_start = header.len() + 8 * layout.height as usize;
+	for y in 0..layout.height as usize {
+		let offset = (data_start + y * exr_block_size(layout)) as u64;
+		header.extend_from_slice(&offset.to_le_bytes());
+	}
+	file.write_all(&header)?;
+	for band in bands {
+		file.write_all(&band)?;
+	}
+	file.flush()?;
+	Ok(())
+}
+
+// PPM
+
+// Convert a band to PPM samples: big endian and without alpha.
+fn ppm_samples(band: Band, layout: ExportLayout) -> Vec<u8> {
+	let rows = to_big_endian(band.rows, layout.sample);
+	match layout.channels {
+		1 => rows,
+		_ => {
+			let rgb_size = 3 * layout.sample.size();
+			rows.chunks_exact(layout.pixel_size())
+				.flat_map(|pixel| &pixel[..rgb_size])
+				.copied()
+				.collect()
+		}
+	}
+}
+
+fn write_ppm(mut file: BufWriter<File>, layout: ExportLayout, bands: InOrder<Vec<u8>>) -> Result<(), ExportError> {
+	let magic = match layout.channels {
+		1 => "P5",
+		_ => "P6",
+	};
+	let max = match layout.sample {
+		SampleType::U8 => 255,
+		_ => 65535,
+	};
+	write!(file, "{}\n{} {}\n{}\n", magic, layout.width, layout.height, max)?;
+	for band in bands {
+		file.write_all(&band)?;
+	}
+	file.flush()?;
+	Ok(())
+}
@@ -0,0 +1,116 @@
+use super::*;
+
+pub use vulkano::image::Dimensions;
+
+/// Size of an image of any kind: 1D, 2D or 3D, with or without array layers, or a cube map.
+///
+/// 2D sizes convert from `(width, height)`, `[width, height]` and `UVec2`,
+/// 3D sizes from `(width, height, depth)`, `[width, height, depth]` and `UVec3`.
+/// The other kinds have constructors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Extent(pub Dimensions);
+
+impl Extent {
+	pub fn d1(width: u32) -> Self {
+		Extent(Dimensions::Dim1d { width })
+	}
+
+	pub fn d1_array(width: u32, layers: u32) -> Self {
+		Extent(Dimensions::Dim1dArray { width, array_layers: layers })
+	}
+
+	pub fn d2<S: Into<UVec2>>(size: S) -> Self {
+		Extent(size.into().into())
+	}
+
+	pub fn d2_array<S: Into<UVec2>>(size: S, layers: u32) -> Self {
+		let size = size.into();
+		Extent(Dimensions::Dim2dArray {
+			width: size.x,
+			height: size.y,
+			array_layers: layers,
+		})
+	}
+
+	pub fn d3<S: Into<UVec3>>(size: S) -> Self {
+		Extent(size.into().into())
+	}
+
+	/// Six square faces of `size` x `size`, in the order +X, -X, +Y, -Y, +Z, -Z.
+	pub fn cube(size: u32) -> Self {
+		Extent(Dimensions::Cubemap { size })
+	}
+
+	/// `layers` cube maps. Needs the `image_cube_array` feature to be viewed as one image.
+	pub fn cube_array(size: u32, layers: u32) -> Self {
+		Extent(Dimensions::CubemapArray { size, array_layers: layers })
+	}
+
+	pub fn dimensions(self) -> Dimensions {
+		self.0
+	}
+
+	/// Width, height and depth of one layer. Unused dimensions are 1.
+	pub fn size(self) -> UVec3 {
+		self.0.into()
+	}
+
+	/// Number of layers, counting each face of a cube map as a layer.
+	pub fn layers(self) -> u32 {
+		self.0.array_layers_with_cube()
+	}
+
+	/// Number of texels in all layers.
+	pub fn texels(self) -> usize {
+		let size = self.size();
+		size.x as usize * size.y as usize * size.z as usize * self.layers() as usize
+	}
+}
+
+impl From<Dimensions> for Extent {
+	fn from(d: Dimensions) -> Self {
+		Extent(d)
+	}
+}
+
+impl From<Extent> for Dimensions {
+	fn from(e: Extent) -> Self {
+		e.0
+	}
+}
+
+impl From<UVec2> for Extent {
+	fn from(size: UVec2) -> Self {
+		Extent::d2(size)
+	}
+}
+
+impl From<(u32, u32)> for Extent {
+	fn from(size: (u32, u32)) -> Self {
+		Extent::d2(size)
+	}
+}
+
+impl From<[u32; 2]> for Extent {
+	fn from(size: [u32; 2]) -> Self {
+		Extent::d2(size)
+	}
+}
+
+impl From<UVec3> for Extent {
+	fn from(size: UVec3) -> Self {
+		Extent::d3(size)
+	}
+}
+
+impl From<(u32, u32, u32)> for Extent {
+	fn from(size: (u32, u32, u32)) -> Self {
+		Extent::d3(size)
+	}
+}
+
+impl From<[u32; 3]> for Extent {
+	fn from(size: [u32; 3]) -> Self {
+		Extent::d3(size)
+	}
+}
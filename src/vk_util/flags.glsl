@@ -0,0 +1,24 @@
+#version 450
+
+// The flags of a stream compaction as 0 or 1, so that their prefix sum counts the kept elements:
+// any nonzero flag keeps its element.
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+layout(set = 0, binding = 0) readonly buffer Input {
+    uint data[];
+};
+
+// the same length as the input
+layout(set = 0, binding = 1) writeonly buffer Output {
+    uint flags[];
+};
+
+void main() {
+    // a grid-stride loop, since the dispatch is capped at a number of workgroups every device takes
+    uint len = uint(data.length());
+    uint stride = gl_NumWorkGroups.x * gl_WorkGroupSize.x;
+    for (uint index = gl_GlobalInvocationID.x; index < len; index += stride) {
+        flags[index] = uint(data[index] != 0);
+    }
+}
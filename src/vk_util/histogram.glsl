@@ -0,0 +1,39 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+// Counts the elements in equally wide bins over the range [lo, hi).
+// Elements outside the range, and NaN, are not counted.
+
+#include "scalar.glsl"
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+layout(set = 0, binding = 0) readonly buffer Input {
+    TYPE data[];
+};
+
+// zeroed beforehand, or holding counts to add to
+layout(set = 0, binding = 1) buffer Counts {
+    uint counts[];
+};
+
+layout(push_constant) uniform PushConstants {
+    TYPE lo;
+    TYPE hi;
+} params;
+
+void main() {
+    // a grid-stride loop, since the dispatch is capped at a number of workgroups every device takes
+    uint len = uint(data.length());
+    uint stride = gl_NumWorkGroups.x * gl_WorkGroupSize.x;
+    for (uint index = gl_GlobalInvocationID.x; index < len; index += stride) {
+        TYPE v = data[index];
+        if (!(v >= params.lo && v < params.hi)) {
+            continue;
+        }
+        // in single precision, so that any range works for integers too
+        uint bins = uint(counts.length());
+        float position = (float(v) - float(params.lo)) / (float(params.hi) - float(params.lo)) * float(bins);
+        atomicAdd(counts[min(uint(position), bins - 1)], 1);
+    }
+}
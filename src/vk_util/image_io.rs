@@ -0,0 +1,119 @@
+use super::*;
+
+pub use image::DynamicImage;
+
+use half::f16;
+use image::ImageBuffer;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::image::ImageAccess;
+use vulkano::sync::GpuFuture;
+
+/// Copying images between the GPU and the host.
+///
+/// Like `upload` and `download`, transfers run on the transfer queue and block until done.
+impl Interface {
+	/// Copy the pixels of `image` to the host, tightly packed in the image's format.
+	/// Layers (and cube faces) follow each other, and each layer holds its slices of a 3D image in order.
+	/// GPU work writing to `image` must have finished (e.g. its fence waited on) before calling this.
+	pub fn read_image_data(&self, image: &Arc<StorageImage<Format>>) -> Vec<u8> {
+		let staging = self.image_buffer(image);
+		let extent = Extent((**image).dimensions());
+
+		let mut builder = self.transfer_command_buffer_builder();
+		builder
+			.copy_image_to_buffer_dimensions(image.clone(), staging.clone(), [0; 3], extent.size().into(), 0, extent.layers(), 0)
+			.unwrap();
+		let finished = builder.build().unwrap().execute(self.transfer_queue()).unwrap();
+		finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+
+		let data = staging.read().unwrap().to_vec();
+		data
+	}
+
+	/// Copy `data` into a new storage image, laid out as `read_image_data` returns it.
+	/// Panics if `data` is not exactly the size of the image.
+	pub fn write_image_data<E: Into<Extent>>(&self, extent: E, format: Format, data: &[u8]) -> Arc<StorageImage<Format>> {
+		let extent = extent.into();
+		assert_eq!(data.len(), image_size(format, extent), "write_image_data: {:?} {:?}", extent, format);
+		let target = self.storage_image(extent, format);
+		let staging = self.cpu_buffer_from_slice(data, BufferUsage::transfer_source());
+
+		let mut builder = self.transfer_command_buffer_builder();
+		builder
+			.copy_buffer_to_image_dimensions(staging, target.clone(), [0; 3], extent.size().into(), 0, extent.layers(), 0)
+			.unwrap();
+		let finished = builder.build().unwrap().execute(self.transfer_queue()).unwrap();
+		finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+		target
+	}
+
+	/// Copy the 2D `image` to the host, as an image of the matching color type.
+	///
+	/// Supports 8 and 16 bit unsigned normalized formats with 1, 2 or 4 channels (RGBA or BGRA),
+	/// and 16 and 32 bit float formats with 1 or 4 channels. `DynamicImage` has no float samples,
+	/// so floats are clamped to [0, 1] and stored as 16 bit. Use `read_image_data` for the floats themselves.
+	/// Panics for other formats.
+	pub fn read_image(&self, image: &Arc<StorageImage<Format>>) -> DynamicImage {
+		use DynamicImage::*;
+		use Format::*;
+		let dimensions = (**image).dimensions();
+		assert!(
+			matches!(dimensions, Dimensions::Dim2d { .. }),
+			"read_image: {:?} is not a 2D image",
+			dimensions
+		);
+		let [w, h] = dimensions.width_height();
+		let data = self.read_image_data(image);
+		let u16s = || -> Vec<u16> { data.chunks_exact(2).map(|s| u16::from_ne_bytes([s[0], s[1]])).collect() };
+		let unorm16 = |v: f32| (v.clamp(0.0, 1.0) * 65535.0).round() as u16;
+		let f16s = || -> Vec<u16> { u16s().into_iter().map(|v| unorm16(f16::from_bits(v).to_f32())).collect() };
+		let f32s = || -> Vec<u16> {
+			data.chunks_exact(4)
+				.map(|s| unorm16(f32::from_ne_bytes([s[0], s[1], s[2], s[3]])))
+				.collect()
+		};
+		// the buffer always has the size of the image
+		match image.format() {
+			R8Unorm => ImageLuma8(ImageBuffer::from_raw(w, h, data).unwrap()),
+			R8G8Unorm => ImageLumaA8(ImageBuffer::from_raw(w, h, data).unwrap()),
+			R8G8B8A8Unorm | R8G8B8A8Srgb => ImageRgba8(ImageBuffer::from_raw(w, h, data).unwrap()),
+			B8G8R8A8Unorm | B8G8R8A8Srgb => ImageBgra8(ImageBuffer::from_raw(w, h, data).unwrap()),
+			R16Unorm => ImageLuma16(ImageBuffer::from_raw(w, h, u16s()).unwrap()),
+			R16G16Unorm => ImageLumaA16(ImageBuffer::from_raw(w, h, u16s()).unwrap()),
+			R16G16B16A16Unorm => ImageRgba16(ImageBuffer::from_raw(w, h, u16s()).unwrap()),
+			R16Sfloat => ImageLuma16(ImageBuffer::from_raw(w, h, f16s()).unwrap()),
+			R16G16B16A16Sfloat => ImageRgba16(ImageBuffer::from_raw(w, h, f16s()).unwrap()),
+			R32Sfloat => ImageLuma16(ImageBuffer::from_raw(w, h, f32s()).unwrap()),
+			R32G32B32A32Sfloat => ImageRgba16(ImageBuffer::from_raw(w, h, f32s()).unwrap()),
+			format => panic!("read_image: unsupported format {:?}", format),
+		}
+	}
+
+	/// Copy `image` into a new storage image of the matching unsigned normalized format.
+	///
+	/// Gray images become single channel (`R8Unorm`, `R16Unorm`), gray with alpha two channel images.
+	/// Color images become RGBA (`R8G8B8A8Unorm`, `R16G16B16A16Unorm`), with opaque alpha added to RGB.
+	/// Formats other than `R8G8B8A8Unorm` need the device to support them for storage images.
+	pub fn write_image(&self, image: &DynamicImage) -> Arc<StorageImage<Format>> {
+		let (format, data) = image_data(image);
+		self.write_image_data(image::GenericImageView::dimensions(image), format, &data)
+	}
+}
+
+// The pixels of `image` in the format `write_image` gives them on the GPU.
+pub(crate) fn image_data(image: &DynamicImage) -> (Format, Vec<u8>) {
+	use DynamicImage::*;
+	let bytes = |samples: &[u16]| -> Vec<u8> { bytemuck::cast_slice(samples).to_vec() };
+	match image {
+		ImageLuma8(i) => (Format::R8Unorm, i.to_vec()),
+		ImageLumaA8(i) => (Format::R8G8Unorm, i.to_vec()),
+		ImageLuma16(i) => (Format::R16Unorm, bytes(i)),
+		ImageLumaA16(i) => (Format::R16G16Unorm, bytes(i)),
+		ImageRgba16(i) => (Format::R16G16B16A16Unorm, bytes(i)),
+		ImageRgb16(i) => {
+			let rgba: Vec<u16> = i.pixels().flat_map(|p| vec![p[0], p[1], p[2], u16::MAX]).collect();
+			(Format::R16G16B16A16Unorm, bytes(&rgba))
+		}
+		ImageRgb8(_) | ImageRgba8(_) | ImageBgr8(_) | ImageBgra8(_) => (Format::R8G8B8A8Unorm, image.to_rgba().into_raw()),
+	}
+}
@@ -0,0 +1,331 @@
+use super::*;
+
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::image::ImageAccess;
+use vulkano::sync::GpuFuture;
+
+mod cs_convolve {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/convolve.glsl",
+	}
+}
+
+mod cs_sobel {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/sobel.glsl",
+	}
+}
+
+mod cs_color {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/color_convert.glsl",
+	}
+}
+
+mod cs_resize {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/resize.glsl",
+	}
+}
+
+/// Format of the images a `FilterChain` works on.
+pub const FILTER_FORMAT: Format = Format::R32G32B32A32Sfloat;
+
+/// Largest accepted blur sigma, which keeps the kernel at no more than 601 weights.
+pub const MAX_BLUR_SIGMA: f32 = 100.0;
+
+/// One step of a `FilterChain`.
+///
+/// Steps parse from text like `blur=2`, `convolve=1,2,1`, `sobel`, `gray` or `resize=640x480`, see `parse`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilterStep {
+	/// Gaussian blur with a standard deviation of `sigma` pixels.
+	GaussianBlur {
+		sigma: f32,
+	},
+	/// Convolution with a separable kernel: `horizontal` filters the rows, then `vertical` the columns.
+	/// The kernels have odd lengths and are centered on the pixel. An empty kernel leaves out its direction.
+	Convolve {
+		horizontal: Vec<f32>,
+		vertical: Vec<f32>,
+	},
+	/// Edge strength of each color channel, by the Sobel operator.
+	Sobel,
+	Color(ColorConversion),
+	/// Scale to `width` x `height`. A zero width or height follows from the other one, keeping the aspect ratio.
+	Resize {
+		width: u32,
+		height: u32,
+	},
+}
+
+/// Per-pixel conversion of the color channels. Alpha is kept.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorConversion {
+	/// Rec. 709 luma in all color channels.
+	Grayscale,
+	/// Decode sRGB to linear intensities, e.g. before filtering photos.
+	SrgbToLinear,
+	LinearToSrgb,
+}
+
+impl FilterStep {
+	/// Parse one step:
+	///
+	/// - `blur=SIGMA`: Gaussian blur, with SIGMA up to `MAX_BLUR_SIGMA`
+	/// - `convolve=W,W,..`: the same kernel along rows and columns;
+	///   `convolve-x=W,W,..` and `convolve-y=W,W,..` along one of them
+	/// - `sobel`: edge strength
+	/// - `gray`, `linear` or `srgb`: color conversion to grayscale, from sRGB to linear, or back
+	/// - `resize=WxH`: scale to W x H pixels, where 0 keeps the aspect ratio
+	pub fn parse(text: &str) -> Result<Self, String> {
+		let text = text.trim();
+		let (name, value) = match text.find('=') {
+			Some(i) => (text[..i].trim(), Some(text[i + 1..].trim())),
+			None => (text, None),
+		};
+		let err = |what: &str| format!("{}: {}", text, what);
+		let kernel = |value: &str| -> Result<Vec<f32>, String> {
+			let weights = value
+				.split(',')
+				.map(|w| match w.trim().parse::<f32>() {
+					Ok(weight) if weight.is_finite() => Ok(weight),
+					_ => Err(err(&format!("invalid weight {:?}", w))),
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			if weights.len() % 2 == 0 {
+				return Err(err("the kernel needs an odd number of weights"));
+			}
+			Ok(weights)
+		};
+		Ok(match (name, value) {
+			("blur", Some(value)) => match value.parse::<f32>() {
+				Ok(sigma) if sigma > 0.0 && sigma <= MAX_BLUR_SIGMA => FilterStep::GaussianBlur { sigma },
+				_ => return Err(err(&format!("sigma must be a number above 0 and at most {}", MAX_BLUR_SIGMA))),
+			},
+			("convolve", Some(value)) => {
+				let weights = kernel(value)?;
+				FilterStep::Convolve {
+					horizontal: weights.clone(),
+					vertical: weights,
+				}
+			}
+			("convolve-x", Some(value)) => FilterStep::Convolve {
+				horizontal: kernel(value)?,
+				vertical: Vec::new(),
+			},
+			("convolve-y", Some(value)) => FilterStep::Convolve {
+				horizontal: Vec::new(),
+				vertical: kernel(value)?,
+			},
+			("resize", Some(value)) => {
+				let mut parts = value.split('x').map(|v| v.trim().parse::<u32>());
+				match (parts.next(), parts.next(), parts.next()) {
+					(Some(Ok(width)), Some(Ok(height)), None) if width > 0 || height > 0 => FilterStep::Resize { width, height },
+					_ => return Err(err("expected WIDTHxHEIGHT")),
+				}
+			}
+			("sobel", None) => FilterStep::Sobel,
+			("gray", None) => FilterStep::Color(ColorConversion::Grayscale),
+			("linear", None) => FilterStep::Color(ColorConversion::SrgbToLinear),
+			("srgb", None) => FilterStep::Color(ColorConversion::LinearToSrgb),
+			("blur", None) | ("convolve", None) | ("convolve-x", None) | ("convolve-y", None) | ("resize", None) => return Err(err("missing value")),
+			("sobel", Some(_)) | ("gray", Some(_)) | ("linear", Some(_)) | ("srgb", Some(_)) => return Err(err("takes no value")),
+			_ => return Err(err("unknown filter")),
+		})
+	}
+
+	/// Parse steps separated by newlines or `;`. Text from `#` to the end of a line is a comment.
+	pub fn parse_chain(text: &str) -> Result<Vec<Self>, String> {
+		text.lines()
+			.map(|line| line.split('#').next().unwrap())
+			.flat_map(|line| line.split(';'))
+			.filter(|step| !step.trim().is_empty())
+			.map(Self::parse)
+			.collect()
+	}
+
+	/// Size of the output for an input of `size`.
+	pub fn output_size(&self, [w, h]: [u32; 2]) -> [u32; 2] {
+		match *self {
+			FilterStep::Resize { width: 0, height } => [((w as u64 * height as u64 + h as u64 / 2) / h as u64).max(1) as u32, height],
+			FilterStep::Resize { width, height: 0 } => [width, ((h as u64 * width as u64 + w as u64 / 2) / w as u64).max(1) as u32],
+			FilterStep::Resize { width, height } => [width, height],
+			_ => [w, h],
+		}
+	}
+}
+
+/// Normalized weights of a Gaussian with standard deviation `sigma`, out to 3 sigma on either side.
+pub fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+	let radius = (3.0 * sigma).ceil() as i32;
+	let weights: Vec<f32> = (-radius..=radius).map(|x| (-(x * x) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+	let total: f32 = weights.iter().sum();
+	weights.iter().map(|w| w / total).collect()
+}
+
+// A step as it is dispatched.
+enum Pass {
+	Convolve {
+		weights: Arc<CpuAccessibleBuffer<[f32]>>,
+		direction: [i32; 2],
+	},
+	Sobel,
+	Color(ColorConversion),
+	Resize(FilterStep),
+}
+
+/// A sequence of image filters on the GPU, set up once and applied to any number of images:
+///
+/// ```ignore
+/// let chain = FilterChain::new(&vk, &FilterStep::parse_chain("linear; blur=2; resize=512x0; srgb")?);
+/// let output = chain.apply(&vk, &image::open(path)?);
+/// ```
+///
+/// All steps work on `FILTER_FORMAT` (RGBA 32 bit float) images, so intermediate values are neither clamped nor rounded.
+pub struct FilterChain {
+	steps: Vec<FilterStep>,
+	passes: Vec<Pass>,
+	convolve: Kernel<cs_convolve::ty::PushConstants>,
+	sobel: Kernel,
+	grayscale: Kernel,
+	srgb_to_linear: Kernel,
+	linear_to_srgb: Kernel,
+	resize: Kernel,
+}
+
+impl FilterChain {
+	pub fn new(vk: &Interface, steps: &[FilterStep]) -> Self {
+		let storage = BufferUsage {
+			storage_buffer: true,
+			..BufferUsage::none()
+		};
+		let mut passes = Vec::new();
+		for step in steps {
+			let mut convolve = |weights: &[f32], direction: [i32; 2]| {
+				if !weights.is_empty() {
+					let weights = vk.cpu_buffer_from_slice(weights, storage);
+					passes.push(Pass::Convolve { weights, direction });
+				}
+			};
+			match step {
+				FilterStep::GaussianBlur { sigma } => {
+					let weights = gaussian_kernel(*sigma);
+					convolve(&weights, [1, 0]);
+					convolve(&weights, [0, 1]);
+				}
+				FilterStep::Convolve { horizontal, vertical } => {
+					convolve(horizontal, [1, 0]);
+					convolve(vertical, [0, 1]);
+				}
+				FilterStep::Sobel => passes.push(Pass::Sobel),
+				FilterStep::Color(conversion) => passes.push(Pass::Color(*conversion)),
+				FilterStep::Resize { .. } => passes.push(Pass::Resize(step.clone())),
+			}
+		}
+
+		let color = |conversion| {
+			let shader = cs_color::Shader::load(vk.device()).unwrap();
+			let spec = cs_color::SpecializationConstants {
+				conversion,
+				..Default::default()
+			};
+			Kernel::with_specialization(vk, &shader.main_entry_point(), spec)
+		};
+		Self {
+			steps: steps.to_vec(),
+			passes,
+			convolve: Kernel::new(vk, &cs_convolve::Shader::load(vk.device()).unwrap().main_entry_point()),
+			sobel: Kernel::new(vk, &cs_sobel::Shader::load(vk.device()).unwrap().main_entry_point()),
+			grayscale: color(0),
+			srgb_to_linear: color(1),
+			linear_to_srgb: color(2),
+			resize: Kernel::new(vk, &cs_resize::Shader::load(vk.device()).unwrap().main_entry_point()),
+		}
+	}
+
+	pub fn steps(&self) -> &[FilterStep] {
+		&self.steps
+	}
+
+	/// Size of the output for an input of `size`.
+	pub fn output_size(&self, size: [u32; 2]) -> [u32; 2] {
+		self.steps.iter().fold(size, |size, step| step.output_size(size))
+	}
+
+	/// Record the dispatches of all steps, each filtering into a new image, and return the last image.
+	/// `src` must be a 2D `FILTER_FORMAT` image. Without steps, `src` is returned.
+	pub fn record(&self, vk: &Interface, builder: &mut AutoCommandBufferBuilder, src: Arc<StorageImage<Format>>) -> Arc<StorageImage<Format>> {
+		assert_eq!(src.format(), FILTER_FORMAT, "filter chain: input must be {:?}", FILTER_FORMAT);
+		let mut src = src;
+		for pass in &self.passes {
+			let [w, h] = src.dimensions().width_height();
+			let [dw, dh] = match pass {
+				Pass::Resize(step) => step.output_size([w, h]),
+				_ => [w, h],
+			};
+			let dst = vk.storage_image((dw, dh), FILTER_FORMAT);
+			let size = [dw, dh, 1];
+			let images = (src, dst.clone());
+			match pass {
+				Pass::Convolve { weights, direction } => {
+					let push_constants = cs_convolve::ty::PushConstants { direction: *direction };
+					self.convolve
+						.record_dispatch(builder, size, (images.0, images.1, weights.clone()), push_constants)
+				}
+				Pass::Sobel => self.sobel.record_dispatch(builder, size, images, ()),
+				Pass::Color(ColorConversion::Grayscale) => self.grayscale.record_dispatch(builder, size, images, ()),
+				Pass::Color(ColorConversion::SrgbToLinear) => self.srgb_to_linear.record_dispatch(builder, size, images, ()),
+				Pass::Color(ColorConversion::LinearToSrgb) => self.linear_to_srgb.record_dispatch(builder, size, images, ()),
+				Pass::Resize(_) => self.resize.record_dispatch(builder, size, images, ()),
+			}
+			src = dst;
+		}
+		src
+	}
+
+	/// Upload `image`, filter it on the compute queue, blocking until done, and return the result.
+	/// Read it back with `Interface::read_image_data`, or `read_image` for 16 bit samples.
+	pub fn apply(&self, vk: &Interface, image: &DynamicImage) -> Arc<StorageImage<Format>> {
+		let (w, h) = image::GenericImageView::dimensions(image);
+		let input = vk.write_image_data((w, h), FILTER_FORMAT, bytemuck::cast_slice(&rgba32f(image)));
+		let mut builder = vk.auto_command_buffer_builder();
+		let output = self.record(vk, &mut builder, input);
+		let finished = builder.build().unwrap().execute(vk.queue()).unwrap();
+		finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+		output
+	}
+}
+
+/// The pixels of `image` as RGBA floats, from 0 to 1.
+pub fn rgba32f(image: &DynamicImage) -> Vec<f32> {
+	use DynamicImage::*;
+	match image {
+		ImageLuma16(_) | ImageLumaA16(_) | ImageRgb16(_) | ImageRgba16(_) => {
+			let (format, data) = image_data(image);
+			let samples: &[u16] = bytemuck::cast_slice(&data);
+			let channels = match format {
+				Format::R16Unorm => 1,
+				Format::R16G16Unorm => 2,
+				_ => 4,
+			};
+			// gray and gray-alpha spread over the color channels
+			samples
+				.chunks_exact(channels)
+				.flat_map(|p| {
+					let v = |i: usize| p[i] as f32 / 65535.0;
+					match channels {
+						1 => [v(0), v(0), v(0), 1.0],
+						2 => [v(0), v(0), v(0), v(1)],
+						_ => [v(0), v(1), v(2), v(3)],
+					}
+				})
+				.collect()
+		}
+		_ => image.to_rgba().into_raw().into_iter().map(|v| v as f32 / 255.0).collect(),
+	}
+}
@@ -1,27 +1,122 @@
 use super::*;
 
 pub use vulkano::command_buffer::AutoCommandBufferBuilder;
-pub use vulkano::device::{Device, Queue};
+pub use vulkano::device::{Device, DeviceExtensions, Features, Queue};
 pub use vulkano::format::Format;
 pub use vulkano::image::StorageImage;
 
-use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
-use vulkano::device::{DeviceExtensions, Features};
-use vulkano::instance::{Instance, InstanceExtensions, PhysicalDevice};
+use std::iter;
+use vulkano::image::ImageAccess;
+use vulkano::instance::{PhysicalDevice, QueueFamily};
 
 pub struct Interface {
 	device: Arc<Device>,
 	queue: Arc<Queue>,
+	transfer_queue: Option<Arc<Queue>>,
+	debug: Option<DebugMessenger>,
 	info: String,
 }
 
+// Device, compute queue and optional dedicated transfer queue.
+type DeviceQueues = (Arc<Device>, Arc<Queue>, Option<Arc<Queue>>);
+
+/// Configures and constructs an `Interface`.
+#[derive(Clone, Debug, Default)]
+pub struct InterfaceBuilder {
+	device: DeviceSelection,
+	requirements: DeviceRequirements,
+	debug: Option<MessageSink>,
+}
+
+impl InterfaceBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Physical device selection policy.
+	/// Overridden by the `VK_PLAYGROUND_DEVICE` environment variable, if set.
+	pub fn device(mut self, selection: DeviceSelection) -> Self {
+		self.device = selection;
+		self
+	}
+
+	/// Features the device must support, construction fails otherwise.
+	pub fn required_features(mut self, features: Features) -> Self {
+		self.requirements.required_features = features;
+		self
+	}
+
+	/// Features enabled when supported, see `Interface::enabled_features`.
+	pub fn optional_features(mut self, features: Features) -> Self {
+		self.requirements.optional_features = features;
+		self
+	}
+
+	/// Extensions the device must support, construction fails otherwise.
+	pub fn required_extensions(mut self, extensions: DeviceExtensions) -> Self {
+		self.requirements.required_extensions = extensions;
+		self
+	}
+
+	/// Extensions enabled when supported, see `Interface::enabled_extensions`.
+	pub fn optional_extensions(mut self, extensions: DeviceExtensions) -> Self {
+		self.requirements.optional_extensions = extensions;
+		self
+	}
+
+	/// Enable debug mode: the Khronos validation layer (when installed) reports to `sink`.
+	pub fn debug(mut self, sink: MessageSink) -> Self {
+		self.debug = Some(sink);
+		self
+	}
+
+	pub fn build(self) -> Result<Interface, InterfaceError> {
+		let (selection, source) = match DeviceSelection::from_env() {
+			Some(sel) => (sel, DEVICE_ENV),
+			None => (self.device, "builder"),
+		};
+		let instance = DebugMessenger::instance(self.debug.is_some())?;
+		let debug = self.debug.map(|sink| DebugMessenger::attach(&instance, sink));
+		let physical = selection.select(&instance)?;
+		let mut info = format!(
+			"{} ({:?}, device #{}, {} from {})",
+			physical.name(),
+			physical.ty(),
+			physical.index(),
+			selection,
+			source
+		);
+		let (device, queue, transfer_queue) = Interface::init_device_queue(physical, &self.requirements)?;
+		if transfer_queue.is_some() {
+			info += " with dedicated transfer queue";
+		}
+		match &debug {
+			Some(d) if d.validation() => info += ", validation enabled",
+			Some(_) => info += ", validation layer not installed",
+			None => (),
+		}
+		Ok(Interface {
+			device,
+			queue,
+			transfer_queue,
+			debug,
+			info,
+		})
+	}
+}
+
 impl Interface {
+	/// Like `try_new_compute`, but panics if no usable Vulkan device is available.
 	pub fn new_compute() -> Self {
-		let instance = Self::init_instance();
-		let physical = Self::init_physical(&instance);
-		let info = format!("{} ({:?})", physical.name(), physical.ty());
-		let (device, queue) = Self::init_device_queue(physical);
-		Self { device, queue, info }
+		Self::try_new_compute().unwrap_or_else(|e| panic!("{}", e))
+	}
+
+	pub fn try_new_compute() -> Result<Self, InterfaceError> {
+		Self::builder().build()
+	}
+
+	pub fn builder() -> InterfaceBuilder {
+		InterfaceBuilder::new()
 	}
 
 	pub fn info(&self) -> &str {
@@ -32,52 +127,116 @@ impl Interface {
 		self.device.clone()
 	}
 
+	/// Features enabled on the device: the required ones plus the supported optional ones.
+	pub fn enabled_features(&self) -> &Features {
+		self.device.enabled_features()
+	}
+
+	/// Extensions enabled on the device: the required ones plus the supported optional ones.
+	pub fn enabled_extensions(&self) -> &DeviceExtensions {
+		self.device.loaded_extensions()
+	}
+
+	/// Whether the Khronos validation layer is active (see `InterfaceBuilder::debug`).
+	pub fn validation_enabled(&self) -> bool {
+		self.debug.as_ref().map(|d| d.validation()).unwrap_or(false)
+	}
+
+	/// Error-severity validation messages received so far. Always empty outside debug mode.
+	pub fn validation_errors(&self) -> Vec<ValidationMessage> {
+		self.debug.as_ref().map(|d| d.errors()).unwrap_or_default()
+	}
+
+	/// Panics if any validation error was reported, e.g. at the end of a test.
+	pub fn assert_no_validation_errors(&self) {
+		let errors = self.validation_errors();
+		if !errors.is_empty() {
+			let list = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n");
+			panic!("{} vulkan validation error(s):\n{}", errors.len(), list);
+		}
+	}
+
+	/// The compute queue.
 	pub fn queue(&self) -> Arc<Queue> {
 		self.queue.clone()
 	}
 
-	pub fn storage_image<D: Into<UVec2>>(&self, dim: D, format: Format) -> Arc<StorageImage<Format>> {
-		let dim: UVec2 = dim.into();
-		StorageImage::new(self.device(), dim.into(), format, Some(self.queue.family())).unwrap()
+	/// A queue on a transfer-only family if the hardware has one, so that copies can overlap with compute.
+	/// Falls back to the compute queue otherwise.
+	pub fn transfer_queue(&self) -> Arc<Queue> {
+		self.transfer_queue.as_ref().unwrap_or(&self.queue).clone()
 	}
 
+	pub fn has_dedicated_transfer_queue(&self) -> bool {
+		self.transfer_queue.is_some()
+	}
+
+	/// All queue families in use, for resources shared between the compute and transfer queue.
+	pub fn queue_families(&self) -> Vec<QueueFamily<'_>> {
+		iter::once(&self.queue).chain(&self.transfer_queue).map(|q| q.family()).collect()
+	}
+
+	/// Storage image of any kind: `(width, height)` for 2D images, or any other `Extent`.
+	pub fn storage_image<E: Into<Extent>>(&self, extent: E, format: Format) -> Arc<StorageImage<Format>> {
+		StorageImage::new(self.device(), extent.into().dimensions(), format, self.queue_families()).unwrap()
+	}
+
+	/// Byte buffer usable for any purpose. See `cpu_buffer` for typed buffers.
 	pub fn cpu_accessible_buffer(&self, size: usize) -> Arc<CpuAccessibleBuffer<[u8]>> {
-		self.cpu_accessible_buffer_from((0..size).map(|_| 0u8))
+		self.cpu_buffer(size, BufferUsage::all())
+	}
+
+	/// Byte buffer holding exactly the pixels of `image`, in its format, to copy them to or from the host.
+	pub fn image_buffer(&self, image: &StorageImage<Format>) -> Arc<CpuAccessibleBuffer<[u8]>> {
+		self.cpu_accessible_buffer(image_size(image.format(), image.dimensions()))
 	}
 
 	pub fn cpu_accessible_buffer_from<I>(&self, data: I) -> Arc<CpuAccessibleBuffer<[u8]>>
 	where
 		I: ExactSizeIterator<Item = u8>,
 	{
-		CpuAccessibleBuffer::from_iter(self.device(), BufferUsage::all(), false, data).unwrap()
+		self.cpu_buffer_from_iter(data, BufferUsage::all())
 	}
 
 	pub fn auto_command_buffer_builder(&self) -> AutoCommandBufferBuilder {
 		AutoCommandBufferBuilder::new(self.device(), self.queue.family()).unwrap()
 	}
 
-	fn init_instance() -> Arc<Instance> {
-		Instance::new(None, &InstanceExtensions::none(), None).expect("create vulkan instance")
-	}
-
-	fn init_physical(instance: &Arc<Instance>) -> PhysicalDevice {
-		PhysicalDevice::enumerate(instance).next().expect("no vulkan device available")
+	/// Command buffer builder for submission to `transfer_queue`.
+	pub fn transfer_command_buffer_builder(&self) -> AutoCommandBufferBuilder {
+		AutoCommandBufferBuilder::new(self.device(), self.transfer_queue().family()).unwrap()
 	}
 
-	fn init_device_queue(physical: PhysicalDevice) -> (Arc<Device>, Arc<Queue>) {
-		let queue_family = physical.queue_families().find(|&q| q.supports_graphics()).unwrap();
+	// Creates a compute queue and, if available, a queue on a transfer-only family
+	// (typically a DMA engine that runs concurrently with compute).
+	fn init_device_queue(physical: PhysicalDevice, requirements: &DeviceRequirements) -> Result<DeviceQueues, InterfaceError> {
+		let (features, extensions) = requirements.negotiate(physical)?;
+		let queue_family = physical
+			.queue_families()
+			.find(|&q| q.supports_compute())
+			.ok_or_else(|| InterfaceError::NoQueueFamily(physical.name().to_string()))?;
+		let transfer_family = physical
+			.queue_families()
+			.find(|&q| q.explicitly_supports_transfers() && !q.supports_graphics() && !q.supports_compute());
 
 		let (device, mut queues) = {
 			Device::new(
 				physical,
-				&Features::none(),
-				&DeviceExtensions::none(),
-				[(queue_family, 0.5)].iter().cloned(),
-			)
-			.unwrap()
+				&features,
+				&extensions,
+				iter::once((queue_family, 1.0)).chain(transfer_family.map(|q| (q, 0.5))),
+			)?
 		};
 
-		let queue = queues.next().unwrap();
-		(device, queue)
+		let queue = queues.next().expect("device created without queue");
+		let transfer_queue = queues.next();
+		Ok((device, queue, transfer_queue))
 	}
 }
+
+/// Size in bytes of an image of `format`, with all its layers tightly packed.
+/// Panics for formats without a fixed pixel size, like compressed formats.
+pub fn image_size<E: Into<Extent>>(format: Format, extent: E) -> usize {
+	let pixel_size = format.size().unwrap_or_else(|| panic!("{:?} has no fixed pixel size", format));
+	pixel_size * extent.into().texels()
+}
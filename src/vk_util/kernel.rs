@@ -0,0 +1,266 @@
+use super::*;
+
+use std::any::type_name;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+use vulkano::buffer::BufferAccess;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::descriptor::descriptor_set::{
+	DescriptorSet, PersistentDescriptorSet, PersistentDescriptorSetBuf, PersistentDescriptorSetBuilder, PersistentDescriptorSetError,
+	PersistentDescriptorSetImg, PersistentDescriptorSetSampler, UnsafeDescriptorSetLayout,
+};
+use vulkano::descriptor::pipeline_layout::{PipelineLayoutAbstract, PipelineLayoutDesc};
+use vulkano::image::ImageViewAccess;
+use vulkano::pipeline::shader::{EntryPointAbstract, SpecializationConstants};
+use vulkano::pipeline::{ComputePipeline, ComputePipelineAbstract};
+use vulkano::sync::GpuFuture;
+
+/// A compute pipeline together with its workgroup size and push constant type.
+///
+/// Created from the entry point of a `vulkano_shaders::shader!` module:
+///
+/// ```ignore
+/// let kernel = Kernel::new(&vk, &cs::Shader::load(vk.device())?.main_entry_point());
+/// kernel.dispatch_2d(&vk, (w, h), (image.clone(),), cs::ty::PushConstants { ... });
+/// ```
+///
+/// `Pc` is the struct vulkano-shaders generates for the shader's push constant block
+/// (e.g. `layout(push_constant) uniform PushConstants { ... }` becomes `cs::ty::PushConstants`),
+/// or `()` for shaders without push constants.
+///
+/// The workgroup size is read from the shader's SPIR-V, where the values of specialization constants
+/// given with `local_size_{x,y,z}_id` replace the declared size. So both of these work:
+///
+/// ```glsl
+/// layout(local_size_x = 64) in;
+/// layout(local_size_x = 8, local_size_y = 8, local_size_x_id = 0, local_size_y_id = 1) in;
+/// ```
+///
+/// The shader module must be loaded on the device of an `Interface`, which records its workgroup size (see `reflect`).
+///
+/// Dispatches are rounded up to whole workgroups, so the shader must skip invocations outside the extent,
+/// for an image e.g. with `if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(img))))) return;`.
+pub struct Kernel<Pc = ()> {
+	pipeline: Arc<dyn ComputePipelineAbstract + Send + Sync>,
+	local_size: [u32; 3],
+	_push_constants: PhantomData<fn(Pc)>,
+}
+
+impl<Pc: Copy + Send + Sync + 'static> Kernel<Pc> {
+	/// Panics if `Pc` is smaller than the shader's push constant block,
+	/// or if the shader was not loaded on the device of `vk`.
+	pub fn new<Cs>(vk: &Interface, entry_point: &Cs) -> Self
+	where
+		Cs: EntryPointAbstract,
+		Cs::PipelineLayout: Clone + Send + Sync + 'static,
+		Cs::SpecializationConstants: Default,
+	{
+		Self::with_specialization(vk, entry_point, Default::default())
+	}
+
+	/// Like `new`, with values for the shader's specialization constants instead of their defaults.
+	///
+	/// `spec` is the `SpecializationConstants` struct vulkano-shaders generates, usually filled in as
+	/// `cs::SpecializationConstants { my_constant: 1, ..Default::default() }`.
+	/// Overriding the constants of `local_size_{x,y,z}_id` changes the workgroup size.
+	pub fn with_specialization<Cs>(vk: &Interface, entry_point: &Cs, spec: Cs::SpecializationConstants) -> Self
+	where
+		Cs: EntryPointAbstract,
+		Cs::PipelineLayout: Clone + Send + Sync + 'static,
+	{
+		let reflected = module_local_size(&vk.device(), entry_point.module())
+			.expect("kernel: no workgroup size recorded for the shader, load it on the device of the Interface");
+		let local_size = local_size(reflected, &spec);
+		let pipeline: Arc<dyn ComputePipelineAbstract + Send + Sync> = Arc::new(ComputePipeline::new(vk.device(), entry_point, &spec).unwrap());
+
+		// vulkano copies the reflected number of bytes from the push constant value, whatever its type.
+		let push_constants_size = (0..pipeline.num_push_constants_ranges())
+			.filter_map(|i| pipeline.push_constants_range(i))
+			.map(|r| r.offset + r.size)
+			.max()
+			.unwrap_or(0);
+		assert!(
+			mem::size_of::<Pc>() >= push_constants_size,
+			"kernel push constants: {} is {} bytes, shader expects {}",
+			type_name::<Pc>(),
+			mem::size_of::<Pc>(),
+			push_constants_size
+		);
+
+		Self {
+			pipeline,
+			local_size,
+			_push_constants: PhantomData,
+		}
+	}
+
+	pub fn pipeline(&self) -> Arc<dyn ComputePipelineAbstract + Send + Sync> {
+		self.pipeline.clone()
+	}
+
+	/// Workgroup size declared by the shader.
+	pub fn local_size(&self) -> [u32; 3] {
+		self.local_size
+	}
+
+	/// Number of workgroups needed to cover `size` invocations, rounded up.
+	/// The shader must bounds-check invocations that fall outside `size`.
+	pub fn group_count(&self, size: [u32; 3]) -> [u32; 3] {
+		[0, 1, 2].map(|i| size[i].div_ceil(self.local_size[i]))
+	}
+
+	/// Descriptor set 0 with `bindings` bound in order: tuple element `i` goes to binding `i`.
+	pub fn descriptor_set<B: Bindings>(&self, bindings: B) -> Arc<dyn DescriptorSet + Send + Sync> {
+		let layout = self.pipeline.descriptor_set_layout(0).expect("kernel has no descriptor set 0");
+		bindings.descriptor_set(layout.clone())
+	}
+
+	/// Record a dispatch covering `size` invocations.
+	pub fn record_dispatch<B: Bindings>(&self, builder: &mut AutoCommandBufferBuilder, size: [u32; 3], bindings: B, push_constants: Pc) {
+		builder
+			.dispatch(self.group_count(size), self.pipeline(), self.descriptor_set(bindings), push_constants)
+			.unwrap();
+	}
+
+	/// Execute a dispatch covering `(w, h)` invocations on the compute queue.
+	pub fn dispatch_2d<B: Bindings>(&self, vk: &Interface, (w, h): (u32, u32), bindings: B, push_constants: Pc) -> impl GpuFuture {
+		let mut builder = vk.auto_command_buffer_builder();
+		self.record_dispatch(&mut builder, [w, h, 1], bindings, push_constants);
+		builder.build().unwrap().execute(vk.queue()).unwrap()
+	}
+}
+
+// The reflected local size, with the values in `spec` for the dimensions that are specialization constants.
+fn local_size<S: SpecializationConstants>(reflected: LocalSize, spec: &S) -> [u32; 3] {
+	let mut size = reflected.size;
+	for (i, id) in reflected.spec_ids.iter().enumerate() {
+		if let Some(entry) = S::descriptors().iter().find(|e| Some(e.constant_id) == *id) {
+			assert_eq!(
+				entry.size,
+				mem::size_of::<u32>(),
+				"kernel: specialization constant {} is a workgroup size, a uint",
+				entry.constant_id
+			);
+			// Safe: the descriptor describes a u32 field of `spec` at this offset.
+			size[i] = unsafe { ptr::read_unaligned((spec as *const S as *const u8).add(entry.offset as usize) as *const u32) };
+		}
+	}
+	size
+}
+
+/// A resource that can be bound to a descriptor of a `Kernel`.
+pub trait Bind: Sized {
+	type Resource;
+	fn bind<R>(
+		self,
+		set: PersistentDescriptorSetBuilder<R>,
+	) -> Result<PersistentDescriptorSetBuilder<(R, Self::Resource)>, PersistentDescriptorSetError>;
+}
+
+impl<F> Bind for Arc<StorageImage<F>>
+where
+	Arc<StorageImage<F>>: ImageViewAccess,
+{
+	type Resource = PersistentDescriptorSetImg<Self>;
+	fn bind<R>(
+		self,
+		set: PersistentDescriptorSetBuilder<R>,
+	) -> Result<PersistentDescriptorSetBuilder<(R, Self::Resource)>, PersistentDescriptorSetError> {
+		set.add_image(self)
+	}
+}
+
+/// A texture, bound to a `texture2D` (or other sampled image) descriptor.
+impl<F> Bind for Arc<ImmutableImage<F>>
+where
+	Arc<ImmutableImage<F>>: ImageViewAccess,
+{
+	type Resource = PersistentDescriptorSetImg<Self>;
+	fn bind<R>(
+		self,
+		set: PersistentDescriptorSetBuilder<R>,
+	) -> Result<PersistentDescriptorSetBuilder<(R, Self::Resource)>, PersistentDescriptorSetError> {
+		set.add_image(self)
+	}
+}
+
+/// Bound to a `sampler` descriptor.
+impl Bind for Arc<Sampler> {
+	type Resource = PersistentDescriptorSetSampler;
+	fn bind<R>(
+		self,
+		set: PersistentDescriptorSetBuilder<R>,
+	) -> Result<PersistentDescriptorSetBuilder<(R, Self::Resource)>, PersistentDescriptorSetError> {
+		set.add_sampler(self)
+	}
+}
+
+impl<T: ?Sized> Bind for Arc<CpuAccessibleBuffer<T>>
+where
+	Arc<CpuAccessibleBuffer<T>>: BufferAccess,
+{
+	type Resource = PersistentDescriptorSetBuf<Self>;
+	fn bind<R>(
+		self,
+		set: PersistentDescriptorSetBuilder<R>,
+	) -> Result<PersistentDescriptorSetBuilder<(R, Self::Resource)>, PersistentDescriptorSetError> {
+		set.add_buffer(self)
+	}
+}
+
+impl<T: ?Sized> Bind for Arc<DeviceLocalBuffer<T>>
+where
+	Arc<DeviceLocalBuffer<T>>: BufferAccess,
+{
+	type Resource = PersistentDescriptorSetBuf<Self>;
+	fn bind<R>(
+		self,
+		set: PersistentDescriptorSetBuilder<R>,
+	) -> Result<PersistentDescriptorSetBuilder<(R, Self::Resource)>, PersistentDescriptorSetError> {
+		set.add_buffer(self)
+	}
+}
+
+impl<T> Bind for UniformBuffer<T>
+where
+	UniformBuffer<T>: BufferAccess,
+{
+	type Resource = PersistentDescriptorSetBuf<Self>;
+	fn bind<R>(
+		self,
+		set: PersistentDescriptorSetBuilder<R>,
+	) -> Result<PersistentDescriptorSetBuilder<(R, Self::Resource)>, PersistentDescriptorSetError> {
+		set.add_buffer(self)
+	}
+}
+
+/// A tuple of resources bound to consecutive bindings of a descriptor set, starting at binding 0.
+pub trait Bindings {
+	fn descriptor_set(self, layout: Arc<UnsafeDescriptorSetLayout>) -> Arc<dyn DescriptorSet + Send + Sync>;
+}
+
+macro_rules! impl_bindings {
+	($($B:ident $i:tt),+) => {
+		impl<$($B: Bind),+> Bindings for ($($B,)+)
+		where
+			PersistentDescriptorSet<impl_bindings!(@nest (); $($B)+)>: DescriptorSet + Send + Sync + 'static,
+		{
+			fn descriptor_set(self, layout: Arc<UnsafeDescriptorSetLayout>) -> Arc<dyn DescriptorSet + Send + Sync> {
+				let set = PersistentDescriptorSet::start(layout);
+				$(let set = self.$i.bind(set).unwrap_or_else(|e| panic!("kernel binding {}: {}", $i, e));)+
+				Arc::new(set.build().unwrap())
+			}
+		}
+	};
+	// Builder resource type after binding each of the given types: `((((), A), B), C)`.
+	(@nest $acc:ty; ) => { $acc };
+	(@nest $acc:ty; $B:ident $($rest:ident)*) => { impl_bindings!(@nest ($acc, <$B as Bind>::Resource); $($rest)*) };
+}
+
+impl_bindings!(A 0);
+impl_bindings!(A 0, B 1);
+impl_bindings!(A 0, B 1, C 2);
+impl_bindings!(A 0, B 1, C 2, D 3);
+impl_bindings!(A 0, B 1, C 2, D 3, E 4);
+impl_bindings!(A 0, B 1, C 2, D 3, E 4, F 5);
@@ -0,0 +1,202 @@
+use super::{Vec3, Vec4};
+use bytemuck::{Pod, Zeroable};
+use std::ops::Mul;
+
+/// GLSL `mat4`: column-major, with the std140 and std430 layout.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(C, align(16))]
+pub struct Mat4 {
+	pub cols: [Vec4; 4],
+}
+
+impl Mat4 {
+	pub const IDENTITY: Self = Self::from_cols(
+		Vec4::new(1.0, 0.0, 0.0, 0.0),
+		Vec4::new(0.0, 1.0, 0.0, 0.0),
+		Vec4::new(0.0, 0.0, 1.0, 0.0),
+		Vec4::new(0.0, 0.0, 0.0, 1.0),
+	);
+
+	pub const fn from_cols(x: Vec4, y: Vec4, z: Vec4, w: Vec4) -> Self {
+		Self { cols: [x, y, z, w] }
+	}
+
+	/// Row `i`.
+	pub fn row(&self, i: usize) -> Vec4 {
+		Vec4::new(self.cols[0][i], self.cols[1][i], self.cols[2][i], self.cols[3][i])
+	}
+
+	pub fn transpose(&self) -> Self {
+		Self::from_cols(self.row(0), self.row(1), self.row(2), self.row(3))
+	}
+
+	pub fn translation(t: Vec3) -> Self {
+		let mut m = Self::IDENTITY;
+		m.cols[3] = t.extend(1.0);
+		m
+	}
+
+	pub fn scale(s: Vec3) -> Self {
+		let mut m = Self::IDENTITY;
+		for i in 0..3 {
+			m.cols[i][i] = s[i];
+		}
+		m
+	}
+
+	/// Counterclockwise rotation by `angle` radians around `axis`, which must have length 1.
+	pub fn rotation(axis: Vec3, angle: f32) -> Self {
+		let m = Mat3::rotation(axis, angle);
+		Self::from_cols(
+			m.col(0).extend(0.0),
+			m.col(1).extend(0.0),
+			m.col(2).extend(0.0),
+			Vec4::new(0.0, 0.0, 0.0, 1.0),
+		)
+	}
+}
+
+impl Default for Mat4 {
+	fn default() -> Self {
+		Self::IDENTITY
+	}
+}
+
+unsafe impl Zeroable for Mat4 {}
+unsafe impl Pod for Mat4 {}
+
+impl Mul<Vec4> for Mat4 {
+	type Output = Vec4;
+
+	fn mul(self, v: Vec4) -> Vec4 {
+		self.cols[0] * v.x + self.cols[1] * v.y + self.cols[2] * v.z + self.cols[3] * v.w
+	}
+}
+
+impl Mul for Mat4 {
+	type Output = Self;
+
+	fn mul(self, rhs: Self) -> Self {
+		Self::from_cols(self * rhs.cols[0], self * rhs.cols[1], self * rhs.cols[2], self * rhs.cols[3])
+	}
+}
+
+/// Columns, as vulkano-shaders generates for `mat4` members.
+impl From<[[f32; 4]; 4]> for Mat4 {
+	fn from(cols: [[f32; 4]; 4]) -> Self {
+		bytemuck::cast(cols)
+	}
+}
+
+impl From<Mat4> for [[f32; 4]; 4] {
+	fn from(m: Mat4) -> Self {
+		bytemuck::cast(m)
+	}
+}
+
+/// GLSL `mat3`: column-major, with the std140 and std430 layout.
+///
+/// Like a `vec3` in a block, each column is padded to 16 bytes, so the matrix takes 48 bytes.
+/// The padding is kept zero.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(C, align(16))]
+pub struct Mat3 {
+	cols: [Vec4; 3],
+}
+
+impl Mat3 {
+	pub const IDENTITY: Self = Self::from_cols(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+	pub const fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self {
+		Self {
+			cols: [
+				Vec4::new(x.x, x.y, x.z, 0.0),
+				Vec4::new(y.x, y.y, y.z, 0.0),
+				Vec4::new(z.x, z.y, z.z, 0.0),
+			],
+		}
+	}
+
+	/// Column `i`.
+	pub fn col(&self, i: usize) -> Vec3 {
+		self.cols[i].truncate()
+	}
+
+	/// Row `i`.
+	pub fn row(&self, i: usize) -> Vec3 {
+		Vec3::new(self.cols[0][i], self.cols[1][i], self.cols[2][i])
+	}
+
+	pub fn transpose(&self) -> Self {
+		Self::from_cols(self.row(0), self.row(1), self.row(2))
+	}
+
+	/// Counterclockwise rotation by `angle` radians around `axis`, which must have length 1.
+	pub fn rotation(axis: Vec3, angle: f32) -> Self {
+		let (sin, cos) = angle.sin_cos();
+		// Rodrigues: cos I + sin [axis]x + (1 - cos) axis axis^T
+		let col = |i: usize| {
+			let mut e = Vec3::ZERO;
+			e[i] = 1.0;
+			e * cos + axis.cross(e) * sin + axis * (axis[i] * (1.0 - cos))
+		};
+		Self::from_cols(col(0), col(1), col(2))
+	}
+}
+
+impl Default for Mat3 {
+	fn default() -> Self {
+		Self::IDENTITY
+	}
+}
+
+unsafe impl Zeroable for Mat3 {}
+unsafe impl Pod for Mat3 {}
+
+impl Mul<Vec3> for Mat3 {
+	type Output = Vec3;
+
+	fn mul(self, v: Vec3) -> Vec3 {
+		self.col(0) * v.x + self.col(1) * v.y + self.col(2) * v.z
+	}
+}
+
+impl Mul for Mat3 {
+	type Output = Self;
+
+	fn mul(self, rhs: Self) -> Self {
+		Self::from_cols(self * rhs.col(0), self * rhs.col(1), self * rhs.col(2))
+	}
+}
+
+impl From<[[f32; 3]; 3]> for Mat3 {
+	fn from(cols: [[f32; 3]; 3]) -> Self {
+		Self::from_cols(cols[0].into(), cols[1].into(), cols[2].into())
+	}
+}
+
+impl From<Mat3> for [[f32; 3]; 3] {
+	fn from(m: Mat3) -> Self {
+		[m.col(0).into(), m.col(1).into(), m.col(2).into()]
+	}
+}
+
+/// Padded columns, as vulkano-shaders generates for `mat3` members of std140 and std430 blocks.
+impl From<Mat3> for [[f32; 4]; 3] {
+	fn from(m: Mat3) -> Self {
+		bytemuck::cast(m)
+	}
+}
+
+/// The upper left 3x3 block.
+impl From<Mat4> for Mat3 {
+	fn from(m: Mat4) -> Self {
+		Self::from_cols(m.cols[0].truncate(), m.cols[1].truncate(), m.cols[2].truncate())
+	}
+}
+
+impl From<Mat3> for Mat4 {
+	fn from(m: Mat3) -> Self {
+		Self::from_cols(m.cols[0], m.cols[1], m.cols[2], Vec4::new(0.0, 0.0, 0.0, 1.0))
+	}
+}
@@ -1,7 +1,37 @@
+pub mod buffer;
+pub mod debug;
+pub mod downsample;
+pub mod error;
+pub mod export;
+pub mod extent;
+pub mod image_io;
+pub mod imgproc;
 pub mod interface;
+pub mod kernel;
+pub mod mat;
+pub mod primitives;
+pub mod reflect;
+pub mod requirements;
+pub mod selection;
+pub mod texture;
 pub mod vec;
 
+pub use buffer::*;
+pub use debug::*;
+pub use downsample::*;
+pub use error::*;
+pub use export::*;
+pub use extent::*;
+pub use image_io::*;
+pub use imgproc::*;
 pub use interface::*;
+pub use kernel::*;
+pub use mat::*;
+pub use primitives::*;
+pub use reflect::*;
+pub use requirements::*;
+pub use selection::*;
+pub use texture::*;
 pub use vec::*;
 
 pub use std::sync::Arc;
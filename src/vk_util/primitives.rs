@@ -0,0 +1,427 @@
+use super::*;
+
+use std::marker::PhantomData;
+use std::ops::Range;
+use vulkano::buffer::TypedBufferAccess;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::sync::GpuFuture;
+
+// One shader module per element type: SCALAR selects the type in scalar.glsl.
+macro_rules! scalar_shaders {
+	($($cs:ident: $path:tt, $scalar:tt;)*) => {
+		$(
+			mod $cs {
+				vulkano_shaders::shader! {
+					ty: "compute",
+					path: $path,
+					define: [("SCALAR", $scalar)],
+				}
+			}
+		)*
+	};
+}
+
+scalar_shaders! {
+	cs_reduce_u32: "src/vk_util/reduce.glsl", "0";
+	cs_reduce_i32: "src/vk_util/reduce.glsl", "1";
+	cs_reduce_f32: "src/vk_util/reduce.glsl", "2";
+	cs_scan_u32: "src/vk_util/scan.glsl", "0";
+	cs_scan_i32: "src/vk_util/scan.glsl", "1";
+	cs_scan_f32: "src/vk_util/scan.glsl", "2";
+	cs_scan_add_u32: "src/vk_util/scan_add.glsl", "0";
+	cs_scan_add_i32: "src/vk_util/scan_add.glsl", "1";
+	cs_scan_add_f32: "src/vk_util/scan_add.glsl", "2";
+	cs_histogram_u32: "src/vk_util/histogram.glsl", "0";
+	cs_histogram_i32: "src/vk_util/histogram.glsl", "1";
+	cs_histogram_f32: "src/vk_util/histogram.glsl", "2";
+}
+
+mod cs_compact {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/compact.glsl",
+	}
+}
+
+mod cs_flags {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/vk_util/flags.glsl",
+	}
+}
+
+// The kernel of whichever of the three modules matches the element type `T`,
+// with the given specialization constants besides the workgroup size.
+macro_rules! scalar_kernel {
+	($vk:expr, $T:ty, [$u32:ident, $i32:ident, $f32:ident] $(, $spec:ident: $value:expr)*) => {
+		match <$T as Scalar>::TYPE {
+			ScalarType::U32 => scalar_kernel!(@load $vk, $u32 $(, $spec: $value)*),
+			ScalarType::I32 => scalar_kernel!(@load $vk, $i32 $(, $spec: $value)*),
+			ScalarType::F32 => scalar_kernel!(@load $vk, $f32 $(, $spec: $value)*),
+		}
+	};
+	(@load $vk:expr, $cs:ident $(, $spec:ident: $value:expr)*) => {{
+		let shader = $cs::Shader::load($vk.device()).unwrap();
+		let spec = $cs::SpecializationConstants {
+			$($spec: $value,)*
+			..Default::default()
+		};
+		Kernel::with_specialization($vk, &shader.main_entry_point(), spec)
+	}};
+}
+
+// The device-local buffers that the primitives take and return.
+type Buffer<T> = Arc<DeviceLocalBuffer<[T]>>;
+
+// Input elements per invocation of a reduction pass, and per invocation of a scan.
+// The blocks a workgroup covers are this many times the workgroup size.
+const REDUCE_ITEMS: u32 = 8;
+const SCAN_ITEMS: u32 = 4;
+
+// The number of workgroups along x that every device takes (the least maxComputeWorkGroupCount[0]).
+// Histograms and compaction loop over the elements in a dispatch of at most this many,
+// while a reduction or scan needs one workgroup per block.
+const MAX_GROUPS: u32 = 65535;
+
+/// Element types of the buffers that `Reduce`, `Scan`, `Histogram` and `Compact` work on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScalarType {
+	U32,
+	I32,
+	F32,
+}
+
+/// An element type of the GPU primitives: `u32`, `i32` or `f32`, which are `uint`, `int` and `float` in GLSL.
+pub trait Scalar: Pod + Send + Sync + PartialOrd + std::fmt::Debug + 'static {
+	const TYPE: ScalarType;
+	/// The largest value, infinity for floats.
+	const MAX: Self;
+	/// The lowest value, minus infinity for floats.
+	const LOWEST: Self;
+}
+
+impl Scalar for u32 {
+	const TYPE: ScalarType = ScalarType::U32;
+	const MAX: Self = u32::MAX;
+	const LOWEST: Self = u32::MIN;
+}
+
+impl Scalar for i32 {
+	const TYPE: ScalarType = ScalarType::I32;
+	const MAX: Self = i32::MAX;
+	const LOWEST: Self = i32::MIN;
+}
+
+impl Scalar for f32 {
+	const TYPE: ScalarType = ScalarType::F32;
+	const MAX: Self = f32::INFINITY;
+	const LOWEST: Self = f32::NEG_INFINITY;
+}
+
+/// How a `Reduce` combines elements.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReduceOp {
+	Sum,
+	Min,
+	Max,
+}
+
+impl ReduceOp {
+	/// The result for no elements: zero, `T::MAX` or `T::LOWEST`.
+	pub fn identity<T: Scalar>(self) -> T {
+		match self {
+			ReduceOp::Sum => T::zeroed(),
+			ReduceOp::Min => T::MAX,
+			ReduceOp::Max => T::LOWEST,
+		}
+	}
+}
+
+/// Sum, minimum or maximum of a buffer on the GPU.
+///
+/// Each pass reduces blocks of a few thousand elements to one, so that a million elements take two passes.
+/// Buffers of more than 65535 blocks, over a hundred million elements, are not supported.
+/// Floats are summed pairwise rather than in order, which rounds differently from a sum on the host.
+pub struct Reduce<T> {
+	op: ReduceOp,
+	kernel: Kernel,
+	_element: PhantomData<fn(T)>,
+}
+
+impl<T: Scalar> Reduce<T> {
+	pub fn new(vk: &Interface, op: ReduceOp) -> Self {
+		let op_id = match op {
+			ReduceOp::Sum => 0,
+			ReduceOp::Min => 1,
+			ReduceOp::Max => 2,
+		};
+		Self {
+			op,
+			kernel: scalar_kernel!(vk, T, [cs_reduce_u32, cs_reduce_i32, cs_reduce_f32], op: op_id, items: REDUCE_ITEMS),
+			_element: PhantomData,
+		}
+	}
+
+	pub fn op(&self) -> ReduceOp {
+		self.op
+	}
+
+	/// Record the passes that reduce `input` to a single element, and return a new buffer holding just that element.
+	/// `input` needs storage buffer usage.
+	/// Panics if `input` is too long.
+	pub fn record(&self, vk: &Interface, builder: &mut AutoCommandBufferBuilder, input: Buffer<T>) -> Buffer<T> {
+		let group_size = self.kernel.local_size()[0];
+		let mut src = input;
+		loop {
+			let groups = block_count(src.len(), group_size * REDUCE_ITEMS, "reduce");
+			let dst = vk.device_local_buffer::<T>(groups as usize, storage_buffer());
+			self.kernel.record_dispatch(builder, [groups * group_size, 1, 1], (src, dst.clone()), ());
+			if groups == 1 {
+				return dst;
+			}
+			src = dst;
+		}
+	}
+
+	/// Upload `data` and reduce it on the compute queue, blocking until done.
+	/// Returns `ReduceOp::identity` for no data.
+	pub fn apply(&self, vk: &Interface, data: &[T]) -> T {
+		if data.is_empty() {
+			return self.op.identity();
+		}
+		let input = upload(vk, data);
+		let mut builder = vk.auto_command_buffer_builder();
+		let output = self.record(vk, &mut builder, input);
+		execute(vk, builder);
+		vk.download(&output)[0]
+	}
+}
+
+/// Whether each element of a prefix sum includes the element itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScanKind {
+	/// `[a, b, c]` becomes `[a, a + b, a + b + c]`.
+	Inclusive,
+	/// `[a, b, c]` becomes `[0, a, a + b]`.
+	Exclusive,
+}
+
+/// Prefix sums of a buffer on the GPU.
+///
+/// Workgroups scan blocks of consecutive elements; unless a single block covers the input,
+/// the block sums are scanned in turn and added to the blocks after them.
+/// Buffers of more than 65535 blocks, over sixty million elements, are not supported.
+pub struct Scan<T> {
+	inclusive: Kernel,
+	exclusive: Kernel,
+	add: Kernel,
+	_element: PhantomData<fn(T)>,
+}
+
+impl<T: Scalar> Scan<T> {
+	pub fn new(vk: &Interface) -> Self {
+		Self {
+			inclusive: scalar_kernel!(vk, T, [cs_scan_u32, cs_scan_i32, cs_scan_f32], exclusive: 0, items: SCAN_ITEMS),
+			exclusive: scalar_kernel!(vk, T, [cs_scan_u32, cs_scan_i32, cs_scan_f32], exclusive: 1, items: SCAN_ITEMS),
+			add: scalar_kernel!(vk, T, [cs_scan_add_u32, cs_scan_add_i32, cs_scan_add_f32], items: SCAN_ITEMS),
+			_element: PhantomData,
+		}
+	}
+
+	/// Record the dispatches that scan `input` into a new buffer of the same length, and return it.
+	/// `input` needs storage buffer usage. Panics if it is too long.
+	pub fn record(&self, vk: &Interface, builder: &mut AutoCommandBufferBuilder, input: Buffer<T>, kind: ScanKind) -> Buffer<T> {
+		let kernel = match kind {
+			ScanKind::Inclusive => &self.inclusive,
+			ScanKind::Exclusive => &self.exclusive,
+		};
+		let group_size = kernel.local_size()[0];
+		let len = input.len();
+		let groups = block_count(len, group_size * SCAN_ITEMS, "scan");
+		let output = vk.device_local_buffer::<T>(len, storage_buffer());
+		let sums = vk.device_local_buffer::<T>(groups as usize, storage_buffer());
+		// the add pass covers the same blocks
+		let size = [groups * group_size, 1, 1];
+		kernel.record_dispatch(builder, size, (input, output.clone(), sums.clone()), ());
+		if groups > 1 {
+			let offsets = self.record(vk, builder, sums, ScanKind::Exclusive);
+			self.add.record_dispatch(builder, size, (output.clone(), offsets), ());
+		}
+		output
+	}
+
+	/// Upload `data` and scan it on the compute queue, blocking until done.
+	pub fn apply(&self, vk: &Interface, data: &[T], kind: ScanKind) -> Vec<T> {
+		if data.is_empty() {
+			return Vec::new();
+		}
+		let input = upload(vk, data);
+		let mut builder = vk.auto_command_buffer_builder();
+		let output = self.record(vk, &mut builder, input, kind);
+		execute(vk, builder);
+		vk.download(&output)
+	}
+}
+
+// Push constants of histogram.glsl.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Bounds<T> {
+	lo: T,
+	hi: T,
+}
+
+/// Counts of the elements of a buffer in equally wide bins, on the GPU.
+///
+/// Bin positions are computed in single precision, also for integers,
+/// so elements of more than 24 bits may land in a neighbouring bin when they are close to a boundary.
+pub struct Histogram<T> {
+	kernel: Kernel<Bounds<T>>,
+}
+
+impl<T: Scalar> Histogram<T> {
+	pub fn new(vk: &Interface) -> Self {
+		Self {
+			kernel: scalar_kernel!(vk, T, [cs_histogram_u32, cs_histogram_i32, cs_histogram_f32]),
+		}
+	}
+
+	/// New buffer of `bins` counts, zeroed by a command recorded in `builder`. Panics for 0 bins.
+	pub fn counts(&self, vk: &Interface, builder: &mut AutoCommandBufferBuilder, bins: usize) -> Buffer<u32> {
+		assert!(bins > 0, "histogram: needs at least one bin");
+		let counts = vk.device_local_buffer::<u32>(bins, storage_buffer());
+		builder.fill_buffer(counts.clone(), 0).unwrap();
+		counts
+	}
+
+	/// Record a dispatch adding the elements of `input` to `counts`, which has one element per bin.
+	/// The bins divide `range` evenly; elements outside of it, and NaN, are not counted.
+	/// `input` needs storage buffer usage.
+	pub fn record(&self, builder: &mut AutoCommandBufferBuilder, input: Buffer<T>, range: Range<T>, counts: Buffer<u32>) {
+		assert!(range.start < range.end, "histogram: empty range {:?}", range);
+		let size = grid_size(&self.kernel, input.len());
+		let bounds = Bounds {
+			lo: range.start,
+			hi: range.end,
+		};
+		self.kernel.record_dispatch(builder, size, (input, counts), bounds);
+	}
+
+	/// Upload `data` and count it into `bins` bins over `range` on the compute queue, blocking until done.
+	/// Panics for 0 bins.
+	pub fn apply(&self, vk: &Interface, data: &[T], range: Range<T>, bins: usize) -> Vec<u32> {
+		assert!(bins > 0, "histogram: needs at least one bin");
+		if data.is_empty() {
+			return vec![0; bins];
+		}
+		let input = upload(vk, data);
+		let mut builder = vk.auto_command_buffer_builder();
+		let counts = self.counts(vk, &mut builder, bins);
+		self.record(&mut builder, input, range, counts.clone());
+		execute(vk, builder);
+		vk.download(&counts)
+	}
+}
+
+/// Stream compaction on the GPU: the flagged elements of a buffer, moved to the front in their original order.
+///
+/// The output index of each element is the exclusive prefix sum of the flags before it,
+/// after each flag is made 0 or 1. Its length is limited by that of a `Scan`.
+pub struct Compact {
+	scan: Scan<u32>,
+	flags: Kernel,
+	kernel: Kernel,
+}
+
+impl Compact {
+	pub fn new(vk: &Interface) -> Self {
+		Self {
+			scan: Scan::new(vk),
+			flags: Kernel::new(vk, &cs_flags::Shader::load(vk.device()).unwrap().main_entry_point()),
+			kernel: Kernel::new(vk, &cs_compact::Shader::load(vk.device()).unwrap().main_entry_point()),
+		}
+	}
+
+	/// Record the dispatches that compact `input`, keeping the elements whose flag is nonzero and dropping those whose flag is 0.
+	///
+	/// Returns a new buffer of the length of `input`, starting with the kept elements,
+	/// and a new buffer holding their number. `input` and `flags` need storage buffer usage.
+	/// Panics unless there is one flag per element, or if `input` is too long.
+	pub fn record<T: Scalar>(
+		&self,
+		vk: &Interface,
+		builder: &mut AutoCommandBufferBuilder,
+		input: Buffer<T>,
+		flags: Buffer<u32>,
+	) -> (Buffer<T>, Buffer<u32>) {
+		let len = input.len();
+		assert_eq!(flags.len(), len, "compact: {} flags for {} elements", flags.len(), len);
+		let size = grid_size(&self.kernel, len);
+		let normalized = vk.device_local_buffer::<u32>(len, storage_buffer());
+		self.flags.record_dispatch(builder, size, (flags, normalized.clone()), ());
+		let offsets = self.scan.record(vk, builder, normalized.clone(), ScanKind::Exclusive);
+		let output = vk.device_local_buffer::<T>(len, storage_buffer());
+		let count = vk.device_local_buffer::<u32>(1, storage_buffer());
+		self.kernel
+			.record_dispatch(builder, size, (input, normalized, offsets, output.clone(), count.clone()), ());
+		(output, count)
+	}
+
+	/// Upload `data` and keep the elements for which `keep` is true, on the compute queue, blocking until done.
+	pub fn apply<T: Scalar>(&self, vk: &Interface, data: &[T], keep: &[bool]) -> Vec<T> {
+		assert_eq!(keep.len(), data.len(), "compact: {} flags for {} elements", keep.len(), data.len());
+		if data.is_empty() {
+			return Vec::new();
+		}
+		let input = upload(vk, data);
+		let flags: Vec<u32> = keep.iter().map(|&k| k as u32).collect();
+		let flags = upload(vk, &flags);
+		let mut builder = vk.auto_command_buffer_builder();
+		let (output, count) = self.record(vk, &mut builder, input, flags);
+		execute(vk, builder);
+		let mut kept = vk.download(&output);
+		kept.truncate(vk.download(&count)[0] as usize);
+		kept
+	}
+}
+
+// The workgroups covering `len` elements in blocks of `block` elements, of which a dispatch takes at most `MAX_GROUPS`.
+fn block_count(len: usize, block: u32, what: &str) -> u32 {
+	let groups = len.div_ceil(block as usize);
+	assert!(
+		groups <= MAX_GROUPS as usize,
+		"{}: {} elements take {} workgroups, more than the {} a dispatch takes",
+		what,
+		len,
+		groups,
+		MAX_GROUPS
+	);
+	groups as u32
+}
+
+// Invocations of a grid-stride `kernel` over `len` elements: one per element, up to `MAX_GROUPS` workgroups.
+fn grid_size<Pc: Copy + Send + Sync + 'static>(kernel: &Kernel<Pc>, len: usize) -> [u32; 3] {
+	let group_size = kernel.local_size()[0];
+	let groups = len.div_ceil(group_size as usize).min(MAX_GROUPS as usize) as u32;
+	[groups * group_size, 1, 1]
+}
+
+fn storage_buffer() -> BufferUsage {
+	BufferUsage {
+		storage_buffer: true,
+		..BufferUsage::none()
+	}
+}
+
+// Copy `data` into a new storage buffer, blocking until done.
+fn upload<T: Pod + Send + Sync>(vk: &Interface, data: &[T]) -> Buffer<T> {
+	let (buffer, uploaded) = vk.upload(data, storage_buffer());
+	uploaded.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+	buffer
+}
+
+// Execute the recorded commands on the compute queue, blocking until done.
+fn execute(vk: &Interface, builder: AutoCommandBufferBuilder) {
+	let finished = builder.build().unwrap().execute(vk.queue()).unwrap();
+	finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+}
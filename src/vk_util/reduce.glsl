@@ -0,0 +1,78 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+// One pass of a reduction: each workgroup combines a block of the input into a single value,
+// the sum, minimum or maximum of its elements. Passes repeat over these partial results until one is left.
+
+#include "scalar.glsl"
+
+// The workgroup size must be a power of two.
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+// 0: sum, 1: minimum, 2: maximum
+layout(constant_id = 3) const uint op = 0;
+// input elements per invocation
+layout(constant_id = 4) const uint items = 8;
+
+layout(set = 0, binding = 0) readonly buffer Input {
+    TYPE data[];
+};
+
+// one per workgroup
+layout(set = 0, binding = 1) writeonly buffer Output {
+    TYPE partials[];
+};
+
+shared TYPE partial[gl_WorkGroupSize.x];
+
+TYPE combine(TYPE a, TYPE b) {
+    switch (op) {
+    case 1:
+        return min(a, b);
+    case 2:
+        return max(a, b);
+    default:
+        return a + b;
+    }
+}
+
+// the value that leaves others unchanged, for the invocations past the end of the input
+TYPE identity() {
+    switch (op) {
+    case 1:
+        return TYPE_MAX;
+    case 2:
+        return TYPE_LOWEST;
+    default:
+        return TYPE(0);
+    }
+}
+
+void main() {
+    uint local = gl_LocalInvocationID.x;
+    uint len = uint(data.length());
+
+    // neighbouring invocations read neighbouring elements
+    uint first = gl_WorkGroupID.x * gl_WorkGroupSize.x * items + local;
+    TYPE acc = identity();
+    for (uint i = 0; i < items; i++) {
+        uint index = first + i * gl_WorkGroupSize.x;
+        if (index < len) {
+            acc = combine(acc, data[index]);
+        }
+    }
+    partial[local] = acc;
+    barrier();
+
+    // tree over the workgroup, halving the active invocations each step
+    for (uint stride = gl_WorkGroupSize.x / 2; stride > 0; stride /= 2) {
+        if (local < stride) {
+            partial[local] = combine(partial[local], partial[local + stride]);
+        }
+        barrier();
+    }
+
+    if (local == 0) {
+        partials[gl_WorkGroupID.x] = partial[0];
+    }
+}
@@ -0,0 +1,169 @@
+//! Workgroup sizes of compute shaders, read from their SPIR-V.
+//!
+//! vulkano reflects neither the workgroup size nor keeps the SPIR-V words of a `ShaderModule`,
+//! and vulkano-shaders only passes them to `ShaderModule::from_words` in its generated `load`.
+//! So the instances of an `Interface` load `vkCreateShaderModule` through `recording_loader`,
+//! which records the workgroup size of every module as it is created, for `Kernel` to look up.
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use vulkano::device::Device;
+use vulkano::instance::loader::{auto_loader, FunctionPointers, Loader, LoadingError};
+use vulkano::pipeline::shader::ShaderModule;
+use vulkano::VulkanObject;
+
+/// Workgroup size of a compute shader.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LocalSize {
+	/// `local_size_{x,y,z}` as declared, 1 where not declared.
+	pub size: [u32; 3],
+	/// Specialization constant of each dimension declared with `local_size_{x,y,z}_id`.
+	/// Its value replaces the declared size.
+	pub spec_ids: [Option<u32>; 3],
+}
+
+const MAGIC: u32 = 0x0723_0203;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_SPEC_CONSTANT_COMPOSITE: u32 = 51;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_DECORATE: u32 = 71;
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BUILT_IN: u32 = 11;
+const BUILT_IN_WORKGROUP_SIZE: u32 = 25;
+
+impl LocalSize {
+	/// Read from the words of a SPIR-V module: the `LocalSize` execution mode,
+	/// overridden by the `WorkgroupSize` built-in where the shader declares one.
+	/// `None` if the module declares no workgroup size, e.g. for a vertex shader, or is not SPIR-V.
+	pub fn reflect(words: &[u32]) -> Option<Self> {
+		if words.len() < 5 || words[0] != MAGIC {
+			return None;
+		}
+		let mut local_size = None;
+		let mut workgroup_size = None;
+		let mut spec_ids = BTreeMap::new();
+		let mut constants = BTreeMap::new();
+		let mut composites = BTreeMap::new();
+
+		let mut rest = &words[5..];
+		while let Some(&first) = rest.first() {
+			let (count, opcode) = ((first >> 16) as usize, first & 0xffff);
+			if count == 0 || count > rest.len() {
+				return None;
+			}
+			let operands = &rest[1..count];
+			match (opcode, operands) {
+				(OP_EXECUTION_MODE, [_entry, EXECUTION_MODE_LOCAL_SIZE, x, y, z]) => local_size = local_size.or(Some([*x, *y, *z])),
+				(OP_DECORATE, [target, DECORATION_SPEC_ID, id]) => {
+					spec_ids.insert(*target, *id);
+				}
+				(OP_DECORATE, [target, DECORATION_BUILT_IN, BUILT_IN_WORKGROUP_SIZE]) => workgroup_size = Some(*target),
+				(OP_CONSTANT, [_type, result, value]) | (OP_SPEC_CONSTANT, [_type, result, value]) => {
+					constants.insert(*result, *value);
+				}
+				(OP_SPEC_CONSTANT_COMPOSITE, [_type, result, x, y, z]) => {
+					composites.insert(*result, [*x, *y, *z]);
+				}
+				_ => (),
+			}
+			rest = &rest[count..];
+		}
+
+		let mut local_size = LocalSize {
+			size: local_size?,
+			spec_ids: [None; 3],
+		};
+		if let Some(dims) = workgroup_size.and_then(|id| composites.get(&id)) {
+			for (i, dim) in dims.iter().enumerate() {
+				if let Some(&value) = constants.get(dim) {
+					local_size.size[i] = value;
+				}
+				local_size.spec_ids[i] = spec_ids.get(dim).copied();
+			}
+		}
+		Some(local_size)
+	}
+}
+
+/// The workgroup size of `module`, if it was created on `device` by an instance with the `recording_loader`.
+pub(crate) fn module_local_size(device: &Device, module: &ShaderModule) -> Option<LocalSize> {
+	MODULES
+		.lock()
+		.unwrap()
+		.get(&(device.internal_object(), module.internal_object()))
+		.copied()
+}
+
+/// The system Vulkan loader, with `vkCreateShaderModule` recording the workgroup size of each module.
+pub(crate) fn recording_loader() -> Result<FunctionPointers<Box<dyn Loader + Send + Sync>>, LoadingError> {
+	Ok(FunctionPointers::new(Box::new(RecordingLoader(auto_loader()?))))
+}
+
+type VoidFunction = extern "system" fn();
+type GetDeviceProcAddr = extern "system" fn(device: usize, name: *const c_char) -> usize;
+type CreateShaderModule = extern "system" fn(device: usize, info: *const ShaderModuleCreateInfo, allocator: *const c_void, module: *mut u64) -> i32;
+
+#[repr(C)]
+struct ShaderModuleCreateInfo {
+	s_type: u32,
+	p_next: *const c_void,
+	flags: u32,
+	code_size: usize,
+	p_code: *const u32,
+}
+
+// Workgroup sizes by device and module handle. A handle that is reused after its module was destroyed is overwritten on creation.
+static MODULES: Mutex<BTreeMap<(usize, u64), LocalSize>> = Mutex::new(BTreeMap::new());
+// The system loader's `vkGetDeviceProcAddr`, which serves all devices.
+static GET_DEVICE_PROC_ADDR: AtomicUsize = AtomicUsize::new(0);
+// The driver's (or top layer's) `vkCreateShaderModule` of each device.
+static CREATE_SHADER_MODULE: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+struct RecordingLoader(&'static FunctionPointers<Box<dyn Loader + Send + Sync>>);
+
+unsafe impl Loader for RecordingLoader {
+	fn get_instance_proc_addr(&self, instance: usize, name: *const c_char) -> VoidFunction {
+		let function = self.0.get_instance_proc_addr(instance, name);
+		// Safe: vulkano passes nul-terminated names.
+		if unsafe { CStr::from_ptr(name) }.to_bytes() == b"vkGetDeviceProcAddr" {
+			GET_DEVICE_PROC_ADDR.store(function as usize, Ordering::SeqCst);
+			// Safe: only the return type differs, a function pointer either way.
+			return unsafe { mem::transmute::<GetDeviceProcAddr, VoidFunction>(get_device_proc_addr) };
+		}
+		function
+	}
+}
+
+extern "system" fn get_device_proc_addr(device: usize, name: *const c_char) -> usize {
+	// Safe: stored before vulkano could call this, and a `vkGetDeviceProcAddr`.
+	let real: GetDeviceProcAddr = unsafe { mem::transmute(GET_DEVICE_PROC_ADDR.load(Ordering::SeqCst)) };
+	let function = real(device, name);
+	if function != 0 && unsafe { CStr::from_ptr(name) }.to_bytes() == b"vkCreateShaderModule" {
+		CREATE_SHADER_MODULE.lock().unwrap().insert(device, function);
+		return create_shader_module as CreateShaderModule as usize;
+	}
+	function
+}
+
+extern "system" fn create_shader_module(device: usize, info: *const ShaderModuleCreateInfo, allocator: *const c_void, module: *mut u64) -> i32 {
+	// Safe: this is only handed out for devices whose `vkCreateShaderModule` was recorded.
+	let real: CreateShaderModule = unsafe { mem::transmute(CREATE_SHADER_MODULE.lock().unwrap()[&device]) };
+	let result = real(device, info, allocator, module);
+	if result == 0 {
+		// Safe: on success the create info and the new handle are valid.
+		let (words, module) = unsafe { (slice::from_raw_parts((*info).p_code, (*info).code_size / 4), *module) };
+		let mut modules = MODULES.lock().unwrap();
+		match LocalSize::reflect(words) {
+			Some(local_size) => modules.insert((device, module), local_size),
+			None => modules.remove(&(device, module)),
+		};
+	}
+	result
+}
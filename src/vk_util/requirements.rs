@@ -0,0 +1,146 @@
+use super::*;
+
+use vulkano::device::{DeviceExtensions, Features, RawDeviceExtensions};
+use vulkano::instance::PhysicalDevice;
+
+/// Device features and extensions requested when building an `Interface`.
+///
+/// Required items must be supported by the physical device, optional ones are enabled only when supported.
+#[derive(Clone, Debug)]
+pub struct DeviceRequirements {
+	pub required_features: Features,
+	pub optional_features: Features,
+	pub required_extensions: DeviceExtensions,
+	pub optional_extensions: DeviceExtensions,
+}
+
+impl Default for DeviceRequirements {
+	fn default() -> Self {
+		Self {
+			required_features: Features::none(),
+			optional_features: Features::none(),
+			required_extensions: DeviceExtensions::none(),
+			optional_extensions: DeviceExtensions::none(),
+		}
+	}
+}
+
+impl DeviceRequirements {
+	/// The features and extensions to enable on `physical`,
+	/// or an error listing every required item it does not support.
+	pub fn negotiate(&self, physical: PhysicalDevice) -> Result<(Features, DeviceExtensions), InterfaceError> {
+		let supported_features = physical.supported_features();
+		let supported_extensions = DeviceExtensions::supported_by_device(physical);
+
+		let mut missing = feature_names(&self.required_features.difference(supported_features));
+		missing.extend(extension_names(&self.required_extensions.difference(&supported_extensions)));
+		if !missing.is_empty() {
+			return Err(InterfaceError::MissingRequirements {
+				device: physical.name().to_string(),
+				missing,
+			});
+		}
+
+		let features = union(&self.required_features, &self.optional_features.intersection(supported_features));
+		let extensions = self
+			.optional_extensions
+			.intersection(&supported_extensions)
+			.union(&self.required_extensions);
+		Ok((features, extensions))
+	}
+}
+
+// Features has intersection and difference but no union.
+fn union(a: &Features, b: &Features) -> Features {
+	let all = Features::all();
+	all.difference(&all.difference(a).difference(b))
+}
+
+// Features has no field iterator, so its fields are listed here. The destructuring in `feature_names` names every one,
+// so a field added to Features does not compile until it is listed too.
+macro_rules! feature_names {
+	($($field:ident,)+) => {
+		/// Names of the features set in `f`, e.g. `["shader_f3264", "shader_int64"]`.
+		pub fn feature_names(f: &Features) -> Vec<String> {
+			let Features { $($field,)+ } = f;
+			let mut names = Vec::new();
+			$(
+				if *$field {
+					names.push(stringify!($field).to_string());
+				}
+			)+
+			names
+		}
+	};
+}
+
+feature_names! {
+	robust_buffer_access,
+	full_draw_index_uint32,
+	image_cube_array,
+	independent_blend,
+	geometry_shader,
+	tessellation_shader,
+	sample_rate_shading,
+	dual_src_blend,
+	logic_op,
+	multi_draw_indirect,
+	draw_indirect_first_instance,
+	depth_clamp,
+	depth_bias_clamp,
+	fill_mode_non_solid,
+	depth_bounds,
+	wide_lines,
+	large_points,
+	alpha_to_one,
+	multi_viewport,
+	sampler_anisotropy,
+	texture_compression_etc2,
+	texture_compression_astc_ldr,
+	texture_compression_bc,
+	occlusion_query_precise,
+	pipeline_statistics_query,
+	vertex_pipeline_stores_and_atomics,
+	fragment_stores_and_atomics,
+	shader_tessellation_and_geometry_point_size,
+	shader_image_gather_extended,
+	shader_storage_image_extended_formats,
+	shader_storage_image_multisample,
+	shader_storage_image_read_without_format,
+	shader_storage_image_write_without_format,
+	shader_uniform_buffer_array_dynamic_indexing,
+	shader_sampled_image_array_dynamic_indexing,
+	shader_storage_buffer_array_dynamic_indexing,
+	shader_storage_image_array_dynamic_indexing,
+	shader_clip_distance,
+	shader_cull_distance,
+	shader_f3264,
+	shader_int64,
+	shader_int16,
+	shader_resource_residency,
+	shader_resource_min_lod,
+	sparse_binding,
+	sparse_residency_buffer,
+	sparse_residency_image2d,
+	sparse_residency_image3d,
+	sparse_residency2_samples,
+	sparse_residency4_samples,
+	sparse_residency8_samples,
+	sparse_residency16_samples,
+	sparse_residency_aliased,
+	variable_multisample_rate,
+	inherited_queries,
+	buffer_device_address,
+	buffer_device_address_capture_replay,
+	buffer_device_address_multi_device,
+}
+
+/// Names of the extensions set in `e` in alphabetical order, e.g. `["VK_KHR_16bit_storage"]`.
+pub fn extension_names(e: &DeviceExtensions) -> Vec<String> {
+	let mut names: Vec<String> = RawDeviceExtensions::from(e)
+		.iter()
+		.map(|name| name.to_string_lossy().into_owned())
+		.collect();
+	names.sort();
+	names
+}
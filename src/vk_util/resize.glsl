@@ -0,0 +1,54 @@
+#version 450
+
+// Scales an image to the size of dst, by any factor.
+// Shrinking averages the source pixels under each destination pixel, weighted by their overlap.
+// Enlarging interpolates linearly between the nearest source pixels.
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+layout(set = 0, binding = 0, rgba32f) uniform readonly image2D src;
+layout(set = 0, binding = 1, rgba32f) uniform writeonly image2D dst;
+
+// Along one axis scaled by `scale` (source size / destination size):
+// the first and one past the last source pixel contributing to destination pixel `i`.
+ivec2 support(float i, float scale) {
+    if (scale > 1.0) {
+        return ivec2(floor(i * scale), ceil((i + 1.0) * scale));
+    }
+    int lo = int(floor((i + 0.5) * scale - 0.5));
+    return ivec2(lo, lo + 2);
+}
+
+// The weight of source pixel `s` in destination pixel `i`.
+float weight(float s, float i, float scale) {
+    if (scale > 1.0) {
+        return max(0.0, min(s + 1.0, (i + 1.0) * scale) - max(s, i * scale));
+    }
+    return max(0.0, 1.0 - abs(s - ((i + 0.5) * scale - 0.5)));
+}
+
+void main() {
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(dst))))) {
+        return;
+    }
+
+    ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    vec2 scale = vec2(imageSize(src)) / vec2(imageSize(dst));
+    ivec2 xs = support(float(pos.x), scale.x);
+    ivec2 ys = support(float(pos.y), scale.y);
+    ivec2 last = imageSize(src) - 1;
+
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int y = ys.x; y < ys.y; y++) {
+        float wy = weight(float(y), float(pos.y), scale.y);
+        for (int x = xs.x; x < xs.y; x++) {
+            float w = wy * weight(float(x), float(pos.x), scale.x);
+            sum += w * imageLoad(src, clamp(ivec2(x, y), ivec2(0), last));
+            total += w;
+        }
+    }
+
+    imageStore(dst, pos, sum / total);
+}
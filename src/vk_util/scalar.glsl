@@ -0,0 +1,16 @@
+// Element type of the buffers, chosen by defining SCALAR: 0 (the default) for uint, 1 for int, 2 for float.
+// TYPE_MAX and TYPE_LOWEST are the largest and lowest values of the type.
+
+#if SCALAR == 2
+#define TYPE float
+#define TYPE_MAX uintBitsToFloat(0x7f800000u)
+#define TYPE_LOWEST uintBitsToFloat(0xff800000u)
+#elif SCALAR == 1
+#define TYPE int
+#define TYPE_MAX 0x7fffffff
+#define TYPE_LOWEST (-0x7fffffff - 1)
+#else
+#define TYPE uint
+#define TYPE_MAX 0xffffffffu
+#define TYPE_LOWEST 0u
+#endif
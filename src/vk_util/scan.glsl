@@ -0,0 +1,71 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+// Prefix sums within blocks: each workgroup scans a block of consecutive elements
+// and writes the block's total, so that a second level can offset the blocks by the sums of those before them.
+
+#include "scalar.glsl"
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+// 0: inclusive, each element is the sum up to and including itself, 1: exclusive, the sum before it
+layout(constant_id = 3) const uint exclusive = 0;
+// consecutive input elements per invocation
+layout(constant_id = 4) const uint items = 4;
+
+layout(set = 0, binding = 0) readonly buffer Input {
+    TYPE data[];
+};
+
+// the same length as the input
+layout(set = 0, binding = 1) writeonly buffer Output {
+    TYPE result[];
+};
+
+// one per workgroup
+layout(set = 0, binding = 2) writeonly buffer BlockSums {
+    TYPE sums[];
+};
+
+shared TYPE partial[gl_WorkGroupSize.x];
+
+void main() {
+    uint local = gl_LocalInvocationID.x;
+    uint len = uint(data.length());
+    uint first = gl_GlobalInvocationID.x * items;
+
+    TYPE total = TYPE(0);
+    for (uint i = 0; i < items; i++) {
+        if (first + i < len) {
+            total += data[first + i];
+        }
+    }
+    partial[local] = total;
+    barrier();
+
+    // inclusive scan of the invocation totals, doubling the distance each step
+    for (uint offset = 1; offset < gl_WorkGroupSize.x; offset *= 2) {
+        TYPE before = local >= offset ? partial[local - offset] : TYPE(0);
+        barrier();
+        partial[local] += before;
+        barrier();
+    }
+
+    TYPE sum = local > 0 ? partial[local - 1] : TYPE(0);
+    for (uint i = 0; i < items; i++) {
+        if (first + i < len) {
+            TYPE v = data[first + i];
+            if (exclusive != 0) {
+                result[first + i] = sum;
+                sum += v;
+            } else {
+                sum += v;
+                result[first + i] = sum;
+            }
+        }
+    }
+
+    if (local == gl_WorkGroupSize.x - 1) {
+        sums[gl_WorkGroupID.x] = partial[local];
+    }
+}
@@ -0,0 +1,32 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+// Second level of a scan: adds to each block of the block-wise scan the sum of all blocks before it.
+// Dispatched like scan.glsl, so that each workgroup covers one block.
+
+#include "scalar.glsl"
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+// consecutive elements per invocation, as in the scan
+layout(constant_id = 3) const uint items = 4;
+
+layout(set = 0, binding = 0) buffer Result {
+    TYPE result[];
+};
+
+// exclusive scan of the block sums, one per workgroup
+layout(set = 0, binding = 1) readonly buffer Offsets {
+    TYPE offsets[];
+};
+
+void main() {
+    uint len = uint(result.length());
+    uint first = gl_GlobalInvocationID.x * items;
+    TYPE offset = offsets[gl_WorkGroupID.x];
+    for (uint i = 0; i < items; i++) {
+        if (first + i < len) {
+            result[first + i] += offset;
+        }
+    }
+}
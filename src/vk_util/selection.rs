@@ -0,0 +1,92 @@
+use super::*;
+
+use std::fmt;
+use std::str::FromStr;
+use vulkano::instance::{Instance, PhysicalDevice, PhysicalDeviceType};
+
+/// Environment variable that overrides the device selection policy.
+/// Accepts `discrete`, `integrated`, `cpu`, a device index, or a device name substring.
+pub const DEVICE_ENV: &str = "VK_PLAYGROUND_DEVICE";
+
+/// Policy for picking a physical device when several are available.
+///
+/// The `Prefer*` policies fall back to the best remaining device
+/// when no device of the preferred type is present.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum DeviceSelection {
+	#[default]
+	PreferDiscrete,
+	PreferIntegrated,
+	/// Prefer a software implementation such as lavapipe or SwiftShader.
+	PreferCpu,
+	/// The n-th device, in the order listed by the Vulkan loader.
+	Index(usize),
+	/// The first device whose name contains this substring (case-insensitive).
+	Name(String),
+}
+
+impl DeviceSelection {
+	/// The policy set by the `VK_PLAYGROUND_DEVICE` environment variable, if any.
+	pub fn from_env() -> Option<Self> {
+		match std::env::var(DEVICE_ENV) {
+			Ok(v) if !v.trim().is_empty() => v.parse().ok(),
+			_ => None,
+		}
+	}
+
+	/// Select a physical device from `instance` according to this policy.
+	pub fn select<'a>(&self, instance: &'a Arc<Instance>) -> Result<PhysicalDevice<'a>, InterfaceError> {
+		use DeviceSelection::*;
+		if PhysicalDevice::enumerate(instance).next().is_none() {
+			return Err(InterfaceError::NoPhysicalDevice);
+		}
+		let found = match self {
+			PreferDiscrete => Self::best(instance, &[PhysicalDeviceType::DiscreteGpu, PhysicalDeviceType::IntegratedGpu]),
+			PreferIntegrated => Self::best(instance, &[PhysicalDeviceType::IntegratedGpu, PhysicalDeviceType::DiscreteGpu]),
+			PreferCpu => Self::best(instance, &[PhysicalDeviceType::Cpu]),
+			Index(i) => PhysicalDevice::from_index(instance, *i),
+			Name(name) => {
+				let name = name.to_lowercase();
+				PhysicalDevice::enumerate(instance).find(|p| p.name().to_lowercase().contains(&name))
+			}
+		};
+		found.ok_or_else(|| InterfaceError::DeviceNotFound(self.clone()))
+	}
+
+	// The first device of the earliest type in `order`,
+	// or the first device of any other type if none matches.
+	fn best<'a>(instance: &'a Arc<Instance>, order: &[PhysicalDeviceType]) -> Option<PhysicalDevice<'a>> {
+		PhysicalDevice::enumerate(instance).min_by_key(|p| order.iter().position(|&t| t == p.ty()).unwrap_or(order.len()))
+	}
+}
+
+impl FromStr for DeviceSelection {
+	type Err = std::convert::Infallible;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		use DeviceSelection::*;
+		let s = s.trim();
+		Ok(match s.to_lowercase().as_str() {
+			"discrete" => PreferDiscrete,
+			"integrated" => PreferIntegrated,
+			"cpu" | "software" => PreferCpu,
+			_ => match s.parse() {
+				Ok(i) => Index(i),
+				Err(_) => Name(s.to_string()),
+			},
+		})
+	}
+}
+
+impl fmt::Display for DeviceSelection {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use DeviceSelection::*;
+		match self {
+			PreferDiscrete => write!(f, "prefer discrete"),
+			PreferIntegrated => write!(f, "prefer integrated"),
+			PreferCpu => write!(f, "prefer cpu"),
+			Index(i) => write!(f, "index {}", i),
+			Name(name) => write!(f, "name {:?}", name),
+		}
+	}
+}
@@ -0,0 +1,28 @@
+#version 450
+
+// Edge strength: the gradient magnitude of each color channel by the Sobel operator.
+// Alpha is copied. Pixels beyond the edges repeat the edge pixels.
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+layout(set = 0, binding = 0, rgba32f) uniform readonly image2D src;
+layout(set = 0, binding = 1, rgba32f) uniform writeonly image2D dst;
+
+ivec2 pos;
+
+vec3 at(int dx, int dy) {
+    return imageLoad(src, clamp(pos + ivec2(dx, dy), ivec2(0), imageSize(src) - 1)).rgb;
+}
+
+void main() {
+    // the dispatch is rounded up to whole workgroups
+    if (any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(dst))))) {
+        return;
+    }
+
+    pos = ivec2(gl_GlobalInvocationID.xy);
+    vec3 gx = (at(1, -1) + 2.0 * at(1, 0) + at(1, 1)) - (at(-1, -1) + 2.0 * at(-1, 0) + at(-1, 1));
+    vec3 gy = (at(-1, 1) + 2.0 * at(0, 1) + at(1, 1)) - (at(-1, -1) + 2.0 * at(0, -1) + at(1, -1));
+
+    imageStore(dst, pos, vec4(sqrt(gx * gx + gy * gy), imageLoad(src, pos).a));
+}
@@ -0,0 +1,258 @@
+use super::*;
+
+pub use vulkano::image::ImmutableImage;
+pub use vulkano::sampler::{BorderColor, Filter, MipmapMode, Sampler, SamplerAddressMode};
+
+use half::f16;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::image::{ImageLayout, ImageUsage, MipmapsCount};
+use vulkano::sync::GpuFuture;
+
+/// How a `Sampler` filters and wraps texture coordinates. The default is bilinear filtering
+/// between mipmap levels (trilinear), clamped to the edge, without anisotropic filtering.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SamplerOptions {
+	/// Filter for magnified and minified texels.
+	pub filter: Filter,
+	/// Filter between mipmap levels.
+	pub mipmap_mode: MipmapMode,
+	/// Handling of coordinates outside [0, 1], in all directions.
+	pub address_mode: SamplerAddressMode,
+	/// Maximum anisotropy, from 1 (none) up to 16. Greater values need the `sampler_anisotropy` feature,
+	/// and are limited to what the device supports.
+	pub max_anisotropy: f32,
+}
+
+impl Default for SamplerOptions {
+	fn default() -> Self {
+		Self {
+			filter: Filter::Linear,
+			mipmap_mode: MipmapMode::Linear,
+			address_mode: SamplerAddressMode::ClampToEdge,
+			max_anisotropy: 1.0,
+		}
+	}
+}
+
+/// Textures: images that shaders sample with filtering.
+///
+/// Bind a texture and its sampler to separate descriptors, declared in GLSL as
+/// `uniform texture2D tex;` and `uniform sampler smp;` and sampled with `texture(sampler2D(tex, smp), uv)`.
+/// This works in compute kernels as well as in graphics pipelines.
+impl Interface {
+	/// Copy `image` into a new texture, in the format `write_image` uses, except that 8-bit color images
+	/// are `R8G8B8A8Srgb`: photos are sRGB encoded, so shaders sample them as linear values.
+	/// With `mipmaps`, the texture has all mipmap levels, computed on the host (in linear space for sRGB).
+	///
+	/// For 8-bit color data that is not sRGB encoded, like normal maps, use `texture_from_data` with `R8G8B8A8Unorm`.
+	pub fn texture(&self, image: &DynamicImage, mipmaps: bool) -> Arc<ImmutableImage<Format>> {
+		let (format, data) = match image_data(image) {
+			(Format::R8G8B8A8Unorm, data) => (Format::R8G8B8A8Srgb, data),
+			other => other,
+		};
+		self.texture_from_data(image::GenericImageView::dimensions(image), format, &data, mipmaps)
+	}
+
+	/// Copy `data` into a new texture, laid out as `read_image_data` returns it.
+	///
+	/// With `mipmaps`, the texture has all mipmap levels, computed on the host with `mipmap_chain`.
+	/// Panics if `data` is not exactly the size of the image, or if `mipmaps` is set for a format
+	/// `mipmap_chain` does not support or a 3D image.
+	pub fn texture_from_data<E: Into<Extent>>(&self, extent: E, format: Format, data: &[u8], mipmaps: bool) -> Arc<ImmutableImage<Format>> {
+		let extent = extent.into();
+		assert_eq!(data.len(), image_size(format, extent), "texture_from_data: {:?} {:?}", extent, format);
+		let levels = if mipmaps { mipmap_chain(format, extent, data) } else { Vec::new() };
+
+		let usage = ImageUsage {
+			transfer_source: true,
+			transfer_destination: true,
+			sampled: true,
+			..ImageUsage::none()
+		};
+		let (texture, init) = ImmutableImage::uninitialized(
+			self.device(),
+			extent.dimensions(),
+			format,
+			MipmapsCount::Specific(1 + levels.len() as u32),
+			usage,
+			ImageLayout::ShaderReadOnlyOptimal,
+			self.queue_families(),
+		)
+		.unwrap();
+
+		// All levels are written by one command buffer, as the initialization can only be used once.
+		let init = Arc::new(init);
+		let mut builder = self.transfer_command_buffer_builder();
+		let mut size = extent.size();
+		for (level, data) in std::iter::once(data).chain(levels.iter().map(Vec::as_slice)).enumerate() {
+			let staging = self.cpu_buffer_from_slice(data, BufferUsage::transfer_source());
+			builder
+				.copy_buffer_to_image_dimensions(staging, init.clone(), [0; 3], size.into(), 0, extent.layers(), level as u32)
+				.unwrap();
+			size = mip_size(size);
+		}
+		let finished = builder.build().unwrap().execute(self.transfer_queue()).unwrap();
+		finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+		texture
+	}
+
+	/// A sampler with the given options.
+	///
+	/// Anisotropic filtering is turned off if the `sampler_anisotropy` feature is not enabled.
+	pub fn sampler(&self, options: SamplerOptions) -> Arc<Sampler> {
+		let max_anisotropy = if self.enabled_features().sampler_anisotropy {
+			let device = self.device();
+			let limit = device.physical_device().limits().max_sampler_anisotropy();
+			options.max_anisotropy.clamp(1.0, limit)
+		} else {
+			1.0
+		};
+		Sampler::new(
+			self.device(),
+			options.filter,
+			options.filter,
+			options.mipmap_mode,
+			options.address_mode,
+			options.address_mode,
+			options.address_mode,
+			0.0,
+			max_anisotropy,
+			0.0,
+			1000.0,
+		)
+		.unwrap()
+	}
+}
+
+/// Number of mipmap levels of a full chain for `extent`, down to 1x1.
+pub fn mip_levels<E: Into<Extent>>(extent: E) -> u32 {
+	let size = extent.into().size();
+	32 - size.x.max(size.y).max(size.z).leading_zeros()
+}
+
+// Size of the next mipmap level: half the size, rounded down, but at least 1.
+fn mip_size(size: UVec3) -> UVec3 {
+	UVec3::new((size.x / 2).max(1), (size.y / 2).max(1), (size.z / 2).max(1))
+}
+
+/// Mipmap levels of the image `data`, below the base level: each is half the size of the previous level
+/// (rounded down, but at least 1), down to 1x1. Layers are filtered independently.
+///
+/// Each level pixel is the average of the pixels of the previous level it covers, partially covered
+/// pixels of odd sizes included. sRGB color channels are averaged in linear space.
+///
+/// Supports 8 and 16 bit unsigned normalized formats and 16 and 32 bit float formats,
+/// with 1, 2 or 4 channels, as 1D or 2D images. Panics for other formats and for 3D images.
+pub fn mipmap_chain<E: Into<Extent>>(format: Format, extent: E, data: &[u8]) -> Vec<Vec<u8>> {
+	let extent = extent.into();
+	let (sample, channels, srgb) = texel_layout(format).unwrap_or_else(|| panic!("mipmap_chain: unsupported format {:?}", format));
+	assert_eq!(extent.size().z, 1, "mipmap_chain: 3D images are not supported");
+	assert_eq!(data.len(), image_size(format, extent), "mipmap_chain: {:?} {:?}", extent, format);
+
+	let color_channels = if srgb { channels.min(3) } else { 0 };
+	let decode = |c: usize, s: &[u8]| -> f32 {
+		let v = match sample {
+			SampleType::U8 => s[0] as f32 / 255.0,
+			SampleType::U16 => u16::from_ne_bytes([s[0], s[1]]) as f32 / 65535.0,
+			SampleType::F16 => f16::from_bits(u16::from_ne_bytes([s[0], s[1]])).to_f32(),
+			SampleType::F32 => f32::from_ne_bytes([s[0], s[1], s[2], s[3]]),
+		};
+		if c < color_channels {
+			srgb_to_linear(v)
+		} else {
+			v
+		}
+	};
+	let encode = |c: usize, v: f32, out: &mut Vec<u8>| {
+		let v = if c < color_channels { linear_to_srgb(v) } else { v };
+		match sample {
+			SampleType::U8 => out.push((v.clamp(0.0, 1.0) * 255.0).round() as u8),
+			SampleType::U16 => out.extend_from_slice(&((v.clamp(0.0, 1.0) * 65535.0).round() as u16).to_ne_bytes()),
+			SampleType::F16 => out.extend_from_slice(&f16::from_f32(v).to_bits().to_ne_bytes()),
+			SampleType::F32 => out.extend_from_slice(&v.to_ne_bytes()),
+		}
+	};
+
+	// Levels as decoded samples, one layer after the other.
+	let mut size = extent.size();
+	let mut samples: Vec<f32> = data
+		.chunks_exact(sample.size())
+		.enumerate()
+		.map(|(i, s)| decode(i % channels, s))
+		.collect();
+	let mut levels = Vec::new();
+	while size.x > 1 || size.y > 1 {
+		let next = mip_size(size);
+		let mut next_samples = Vec::with_capacity(next.x as usize * next.y as usize * channels * extent.layers() as usize);
+		for layer in samples.chunks_exact(size.x as usize * size.y as usize * channels) {
+			for y in 0..next.y {
+				let ys = covered(y, size.y, next.y);
+				for x in 0..next.x {
+					let xs = covered(x, size.x, next.x);
+					let count = (ys.len() * xs.len()) as f32;
+					for c in 0..channels {
+						let sum: f32 = ys
+							.clone()
+							.flat_map(|sy| xs.clone().map(move |sx| sy as usize * size.x as usize + sx as usize))
+							.map(|i| layer[i * channels + c])
+							.sum();
+						next_samples.push(sum / count);
+					}
+				}
+			}
+		}
+		let mut bytes = Vec::with_capacity(next_samples.len() * sample.size());
+		for (i, &v) in next_samples.iter().enumerate() {
+			encode(i % channels, v, &mut bytes);
+		}
+		levels.push(bytes);
+		samples = next_samples;
+		size = next;
+	}
+	levels
+}
+
+// Pixels of a row or column of `size` pixels covered by pixel `i` of a row or column of `next_size` pixels.
+fn covered(i: u32, size: u32, next_size: u32) -> std::ops::Range<u32> {
+	let start = i * size / next_size;
+	let end = ((i + 1) * size).div_ceil(next_size);
+	start..end
+}
+
+// Sample type, number of channels and whether the color channels are sRGB encoded.
+fn texel_layout(format: Format) -> Option<(SampleType, usize, bool)> {
+	use Format::*;
+	use SampleType::*;
+	Some(match format {
+		R8Unorm => (U8, 1, false),
+		R8G8Unorm => (U8, 2, false),
+		R8G8B8A8Unorm | B8G8R8A8Unorm => (U8, 4, false),
+		R8G8B8A8Srgb | B8G8R8A8Srgb => (U8, 4, true),
+		R16Unorm => (U16, 1, false),
+		R16G16Unorm => (U16, 2, false),
+		R16G16B16A16Unorm => (U16, 4, false),
+		R16Sfloat => (F16, 1, false),
+		R16G16Sfloat => (F16, 2, false),
+		R16G16B16A16Sfloat => (F16, 4, false),
+		R32Sfloat => (F32, 1, false),
+		R32G32Sfloat => (F32, 2, false),
+		R32G32B32A32Sfloat => (F32, 4, false),
+		_ => return None,
+	})
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+	if v <= 0.04045 {
+		v / 12.92
+	} else {
+		((v + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+fn linear_to_srgb(v: f32) -> f32 {
+	if v <= 0.003_130_8 {
+		v * 12.92
+	} else {
+		1.055 * v.powf(1.0 / 2.4) - 0.055
+	}
+}
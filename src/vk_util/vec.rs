@@ -1,19 +1,323 @@
+use bytemuck::{Pod, Zeroable};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
 use vulkano::image::Dimensions;
+use vulkano::pipeline::vertex::{VertexMember, VertexMemberTy};
 
-#[derive(Copy, Clone, Debug)]
-pub struct UVec2(u32, u32);
+// A vector type with components of type `$t`, aligned like the GLSL type in std140 and std430 blocks.
+macro_rules! vector {
+	($(#[$doc:meta])* $name:ident($t:ty; $n:literal), align $align:literal, $vertex_ty:ident, $($field:ident),+) => {
+		$(#[$doc])*
+		#[derive(Copy, Clone, Debug, Default, PartialEq)]
+		#[repr(C, align($align))]
+		pub struct $name {
+			$(pub $field: $t),+
+		}
+
+		impl $name {
+			pub const ZERO: Self = Self::splat(0 as $t);
+
+			pub const fn new($($field: $t),+) -> Self {
+				Self { $($field),+ }
+			}
+
+			/// All components equal to `v`.
+			pub const fn splat(v: $t) -> Self {
+				Self { $($field: v),+ }
+			}
+
+			pub fn dot(self, rhs: Self) -> $t {
+				0 as $t $(+ self.$field * rhs.$field)+
+			}
+
+			pub fn to_array(self) -> [$t; $n] {
+				self.into()
+			}
+		}
+
+		unsafe impl Zeroable for $name {}
+		unsafe impl Pod for $name {}
+
+		unsafe impl VertexMember for $name {
+			fn format() -> (VertexMemberTy, usize) {
+				(VertexMemberTy::$vertex_ty, $n)
+			}
+		}
+
+		impl From<[$t; $n]> for $name {
+			fn from(v: [$t; $n]) -> Self {
+				bytemuck::cast(v)
+			}
+		}
+
+		impl From<$name> for [$t; $n] {
+			fn from(v: $name) -> Self {
+				bytemuck::cast(v)
+			}
+		}
+
+		impl Index<usize> for $name {
+			type Output = $t;
+
+			fn index(&self, i: usize) -> &$t {
+				&bytemuck::cast_ref::<Self, [$t; $n]>(self)[i]
+			}
+		}
+
+		impl IndexMut<usize> for $name {
+			fn index_mut(&mut self, i: usize) -> &mut $t {
+				&mut bytemuck::cast_mut::<Self, [$t; $n]>(self)[i]
+			}
+		}
+
+		// Component-wise, like GLSL.
+		vector!(@op $name, $t, Add, add, AddAssign, add_assign, +, $($field),+);
+		vector!(@op $name, $t, Sub, sub, SubAssign, sub_assign, -, $($field),+);
+		vector!(@op $name, $t, Mul, mul, MulAssign, mul_assign, *, $($field),+);
+		vector!(@op $name, $t, Div, div, DivAssign, div_assign, /, $($field),+);
+	};
+
+	(@op $name:ident, $t:ty, $Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident, $sym:tt, $($field:ident),+) => {
+		impl $Op for $name {
+			type Output = Self;
+
+			fn $op(self, rhs: Self) -> Self {
+				Self { $($field: self.$field $sym rhs.$field),+ }
+			}
+		}
+
+		impl $Op<$t> for $name {
+			type Output = Self;
+
+			fn $op(self, rhs: $t) -> Self {
+				Self { $($field: self.$field $sym rhs),+ }
+			}
+		}
+
+		impl $OpAssign for $name {
+			fn $op_assign(&mut self, rhs: Self) {
+				*self = *self $sym rhs;
+			}
+		}
+
+		impl $OpAssign<$t> for $name {
+			fn $op_assign(&mut self, rhs: $t) {
+				*self = *self $sym rhs;
+			}
+		}
+	};
+}
+
+// A 3 component vector padded to 16 bytes, like a GLSL `vec3` in a block, converting to and from the packed `$packed`.
+macro_rules! padded {
+	($(#[$doc:meta])* $name:ident($t:ty), $packed:ident) => {
+		$(#[$doc])*
+		#[derive(Copy, Clone, Debug, Default, PartialEq)]
+		#[repr(C, align(16))]
+		pub struct $name {
+			pub x: $t,
+			pub y: $t,
+			pub z: $t,
+			// kept zero
+			pad: $t,
+		}
+
+		impl $name {
+			pub const ZERO: Self = Self::new(0 as $t, 0 as $t, 0 as $t);
+
+			pub const fn new(x: $t, y: $t, z: $t) -> Self {
+				Self { x, y, z, pad: 0 as $t }
+			}
+
+			pub fn to_array(self) -> [$t; 3] {
+				[self.x, self.y, self.z]
+			}
+		}
+
+		unsafe impl Zeroable for $name {}
+		unsafe impl Pod for $name {}
+
+		impl From<$packed> for $name {
+			fn from(v: $packed) -> Self {
+				Self::new(v.x, v.y, v.z)
+			}
+		}
+
+		impl From<$name> for $packed {
+			fn from(v: $name) -> Self {
+				Self::new(v.x, v.y, v.z)
+			}
+		}
+
+		impl From<[$t; 3]> for $name {
+			fn from(v: [$t; 3]) -> Self {
+				Self::new(v[0], v[1], v[2])
+			}
+		}
+	};
+}
+
+// Methods of signed vectors.
+macro_rules! signed {
+	($name:ident, $($field:ident),+) => {
+		impl Neg for $name {
+			type Output = Self;
+
+			fn neg(self) -> Self {
+				Self { $($field: -self.$field),+ }
+			}
+		}
+	};
+}
+
+// Methods of float vectors.
+macro_rules! float {
+	($name:ident, $($field:ident),+) => {
+		signed!($name, $($field),+);
+
+		impl $name {
+			pub fn length(self) -> f32 {
+				self.dot(self).sqrt()
+			}
+
+			/// This vector scaled to length 1.
+			pub fn normalize(self) -> Self {
+				self / self.length()
+			}
+
+			/// Linear interpolation from `self` (at `t = 0`) to `rhs` (at `t = 1`).
+			pub fn lerp(self, rhs: Self, t: f32) -> Self {
+				self + (rhs - self) * t
+			}
+		}
+
+		impl Mul<$name> for f32 {
+			type Output = $name;
+
+			fn mul(self, rhs: $name) -> $name {
+				rhs * self
+			}
+		}
+	};
+}
+
+vector!(
+	/// GLSL `vec2`.
+	Vec2(f32; 2), align 8, F32, x, y
+);
+vector!(
+	/// GLSL `vec3`.
+	///
+	/// Unlike GLSL, `Vec3` is 4-byte aligned, so that it has no padding and packs tightly in vertex buffers.
+	/// Do not use it in `#[repr(C)]` structs that mirror uniform or storage blocks: there a `vec3` starts
+	/// at a multiple of 16 bytes and arrays of `vec3` have a 16 byte stride, which `Vec3A` matches.
+	Vec3(f32; 3), align 4, F32, x, y, z
+);
+vector!(
+	/// GLSL `vec4`.
+	Vec4(f32; 4), align 16, F32, x, y, z, w
+);
+vector!(
+	/// GLSL `ivec2`.
+	IVec2(i32; 2), align 8, I32, x, y
+);
+vector!(
+	/// GLSL `uvec2`, also the size of 2D images.
+	UVec2(u32; 2), align 8, U32, x, y
+);
+vector!(
+	/// GLSL `uvec3`, also the size of 3D images and of dispatches.
+	/// Aligned like `Vec3`, so not for uniform or storage blocks either: use `UVec3A` there.
+	UVec3(u32; 3), align 4, U32, x, y, z
+);
+padded!(
+	/// GLSL `vec3` in uniform and storage blocks: 16-byte aligned and padded to 16 bytes, so arrays have its stride.
+	///
+	/// GLSL places a scalar that follows a `vec3` member in its last 4 bytes, which the padding takes here:
+	/// declare the scalar before the `vec3` in the block, or use a `Vec4`.
+	Vec3A(f32), Vec3
+);
+padded!(
+	/// GLSL `uvec3` in uniform and storage blocks, laid out like `Vec3A`.
+	UVec3A(u32), UVec3
+);
+
+float!(Vec2, x, y);
+float!(Vec3, x, y, z);
+float!(Vec4, x, y, z, w);
+signed!(IVec2, x, y);
+
+impl Vec3 {
+	pub fn cross(self, rhs: Self) -> Self {
+		Self::new(
+			self.y * rhs.z - self.z * rhs.y,
+			self.z * rhs.x - self.x * rhs.z,
+			self.x * rhs.y - self.y * rhs.x,
+		)
+	}
+
+	/// `Vec4` with this as `xyz`.
+	pub fn extend(self, w: f32) -> Vec4 {
+		Vec4::new(self.x, self.y, self.z, w)
+	}
+}
+
+impl Vec4 {
+	/// The first three components.
+	pub fn truncate(self) -> Vec3 {
+		Vec3::new(self.x, self.y, self.z)
+	}
+}
 
 impl From<(u32, u32)> for UVec2 {
 	fn from(v: (u32, u32)) -> Self {
-		Self(v.0, v.1)
+		Self::new(v.0, v.1)
+	}
+}
+
+impl From<(u32, u32, u32)> for UVec3 {
+	fn from(v: (u32, u32, u32)) -> Self {
+		Self::new(v.0, v.1, v.2)
 	}
 }
 
-impl Into<Dimensions> for UVec2 {
-	fn into(self) -> Dimensions {
-		Dimensions::Dim2d {
-			width: self.0,
-			height: self.1,
+impl From<UVec2> for Vec2 {
+	fn from(v: UVec2) -> Self {
+		Self::new(v.x as f32, v.y as f32)
+	}
+}
+
+impl From<IVec2> for Vec2 {
+	fn from(v: IVec2) -> Self {
+		Self::new(v.x as f32, v.y as f32)
+	}
+}
+
+impl From<UVec2> for Dimensions {
+	fn from(v: UVec2) -> Self {
+		Dimensions::Dim2d { width: v.x, height: v.y }
+	}
+}
+
+impl From<UVec3> for Dimensions {
+	fn from(v: UVec3) -> Self {
+		Dimensions::Dim3d {
+			width: v.x,
+			height: v.y,
+			depth: v.z,
 		}
 	}
 }
+
+/// Width and height of any image dimensions.
+impl From<Dimensions> for UVec2 {
+	fn from(d: Dimensions) -> Self {
+		d.width_height().into()
+	}
+}
+
+/// Width, height and depth of any image dimensions.
+impl From<Dimensions> for UVec3 {
+	fn from(d: Dimensions) -> Self {
+		d.width_height_depth().into()
+	}
+}
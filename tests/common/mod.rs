@@ -0,0 +1,54 @@
+//! Support for integration tests on machines without a GPU.
+//!
+//! Tests run on a CPU implementation (lavapipe, SwiftShader) when one is installed,
+//! or on whatever device `VK_PLAYGROUND_DEVICE` selects. They are skipped when no
+//! Vulkan implementation is available at all. To force a specific ICD, e.g.:
+//!
+//!     VK_ICD_FILENAMES=/usr/share/vulkan/icd.d/lvp_icd.x86_64.json cargo test
+#![allow(dead_code)]
+
+use vulkan_playground::*;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::sync::GpuFuture;
+
+/// An `Interface` on a software device with validation enabled,
+/// or `None` if no Vulkan implementation is installed.
+pub fn interface() -> Option<Interface> {
+	interface_with(Interface::builder())
+}
+
+/// Like `interface`, with additional settings such as optional features.
+///
+/// Only a missing Vulkan implementation skips the test: any other error,
+/// such as a failure to create the device, is a bug that fails it.
+pub fn interface_with(builder: InterfaceBuilder) -> Option<Interface> {
+	match builder.device(DeviceSelection::PreferCpu).debug(MessageSink::Stderr).build() {
+		Ok(vk) => Some(vk),
+		Err(e @ InterfaceError::Instance(_)) | Err(e @ InterfaceError::NoPhysicalDevice) => {
+			eprintln!("skipping test: {}", e);
+			None
+		}
+		Err(e) => panic!("{}", e),
+	}
+}
+
+/// `common::interface()`, or `common::interface_with(builder)`,
+/// returning from the calling test if Vulkan is not available.
+macro_rules! vk_or_skip {
+	() => {
+		vk_or_skip!(Interface::builder())
+	};
+	($builder:expr) => {
+		match common::interface_with($builder) {
+			Some(vk) => vk,
+			None => return,
+		}
+	};
+}
+
+/// Build the commands, execute them on the compute queue and wait for completion.
+pub fn run(vk: &Interface, builder: AutoCommandBufferBuilder) {
+	let command_buffer = builder.build().unwrap();
+	let finished = command_buffer.execute(vk.queue()).unwrap();
+	finished.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+}
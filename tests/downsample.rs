@@ -0,0 +1,153 @@
+#[macro_use]
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+use vulkan_playground::*;
+use vulkano::format::AcceptsPixels;
+
+#[test]
+fn box_rgba8() {
+	let vk = vk_or_skip!();
+	let (w, h, factor) = (12, 9, 3);
+	let src: Vec<[u8; 4]> = (0..h)
+		.flat_map(|y| (0..w).map(move |x| [(x * 20) as u8, (y * 30) as u8, (x * y) as u8, 255]))
+		.collect();
+	let got = downsample(&vk, DownsampleFilter::Box, Format::R8G8B8A8Unorm, (w, h), factor, &src);
+
+	for y in 0..h / factor {
+		for x in 0..w / factor {
+			for c in 0..4 {
+				let mut sum = 0.0;
+				for sy in y * factor..(y + 1) * factor {
+					for sx in x * factor..(x + 1) * factor {
+						sum += src[(sy * w + sx) as usize][c] as f32;
+					}
+				}
+				let want = sum / (factor * factor) as f32;
+				let got = got[(y * w / factor + x) as usize][c] as f32;
+				assert!((got - want).abs() <= 1.0, "({}, {}) channel {}: got {}, want {}", x, y, c, got, want);
+			}
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn box_rgba32f() {
+	let vk = vk_or_skip!();
+	let (w, h) = (10, 6);
+	let src: Vec<[f32; 4]> = (0..h)
+		.flat_map(|y| (0..w).map(move |x| [x as f32, y as f32, (x * y) as f32, 1.0]))
+		.collect();
+	let got = downsample(&vk, DownsampleFilter::Box, Format::R32G32B32A32Sfloat, (w, h), 2, &src);
+
+	for y in 0..h / 2 {
+		for x in 0..w / 2 {
+			let (x0, y0) = ((2 * x) as f32, (2 * y) as f32);
+			let want = [
+				x0 + 0.5,
+				y0 + 0.5,
+				(x0 * y0 + x0 * (y0 + 1.0) + (x0 + 1.0) * y0 + (x0 + 1.0) * (y0 + 1.0)) / 4.0,
+				1.0,
+			];
+			let got = got[(y * w / 2 + x) as usize];
+			assert!(
+				got.iter().zip(&want).all(|(g, w)| (g - w).abs() < 1e-5),
+				"({}, {}): {:?} != {:?}",
+				x,
+				y,
+				got,
+				want
+			);
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn box_r32f() {
+	let vk = vk_or_skip!();
+	let (w, h) = (8, 4);
+	let src: Vec<f32> = (0..w * h).map(|i| i as f32).collect();
+	let got = downsample(&vk, DownsampleFilter::Box, Format::R32Sfloat, (w, h), 2, &src);
+
+	for y in 0..h / 2 {
+		for x in 0..w / 2 {
+			let i = 2 * y * w + 2 * x;
+			let want = (src[i as usize] + src[(i + 1) as usize] + src[(i + w) as usize] + src[(i + w + 1) as usize]) / 4.0;
+			assert_eq!(got[(y * w / 2 + x) as usize], want, "({}, {})", x, y);
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn lanczos_rgba32f() {
+	let vk = vk_or_skip!();
+	let (w, h, factor) = (32, 32, 4);
+
+	// The weights are normalized, also where the filter is cut off at the edges.
+	let constant = vec![[0.25f32; 4]; (w * h) as usize];
+	let got = downsample(&vk, DownsampleFilter::Lanczos3, Format::R32G32B32A32Sfloat, (w, h), factor, &constant);
+	assert!(got.iter().flatten().all(|&v| (v - 0.25).abs() < 1e-5), "{:?}", got);
+
+	// Away from the edges, the symmetric filter reproduces a linear ramp.
+	let ramp: Vec<[f32; 4]> = (0..h).flat_map(|_| (0..w).map(|x| [x as f32 + 0.5, 0.0, 0.0, 1.0])).collect();
+	let got = downsample(&vk, DownsampleFilter::Lanczos3, Format::R32G32B32A32Sfloat, (w, h), factor, &ramp);
+	for x in 3..5 {
+		let want = (x as f32 + 0.5) * factor as f32;
+		let got = got[(4 * w / factor + x) as usize][0];
+		assert!((got - want).abs() < 1e-3, "x={}: got {}, want {}", x, got, want);
+	}
+	vk.assert_no_validation_errors();
+}
+
+// Linear intensities are averaged, then sRGB encoded: half black and half white is 188, not 128.
+#[test]
+fn box_srgb() {
+	let vk = vk_or_skip!();
+	let linear = [[0.0f32, 0.0, 0.0, 1.0], [1.0, 1.0, 1.0, 1.0], [0.0, 0.0, 0.0, 1.0], [1.0, 1.0, 1.0, 1.0]];
+	let bits: Vec<u16> = linear.iter().flatten().map(|&v| half::f16::from_f32(v).to_bits()).collect();
+	let src = vk.write_image_data((2, 2), Format::R16G16B16A16Sfloat, bytemuck::cast_slice(&bits));
+	let dst = vk.storage_image((1, 1), Format::R8G8B8A8Unorm);
+	let mut builder = vk.auto_command_buffer_builder();
+	Downsample::new(&vk, DownsampleFilter::Box).record(&mut builder, src, dst.clone());
+	common::run(&vk, builder);
+	let got = vk.read_image_data(&dst);
+	assert!(got[..3].iter().all(|&v| (v as i32 - 188).abs() <= 1), "{:?}", got);
+	assert_eq!(got[3], 255);
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn non_integer_factor() {
+	let vk = vk_or_skip!();
+	let downsample = Downsample::new(&vk, DownsampleFilter::Box);
+	let src = vk.storage_image((10, 10), Format::R8G8B8A8Unorm);
+	let dst = vk.storage_image((3, 3), Format::R8G8B8A8Unorm);
+	let mut builder = vk.auto_command_buffer_builder();
+	let result = panic::catch_unwind(AssertUnwindSafe(|| downsample.record(&mut builder, src, dst)));
+	assert!(result.is_err());
+}
+
+// Filter the w x h image `src` down by `factor` and return the result.
+fn downsample<T>(vk: &Interface, filter: DownsampleFilter, format: Format, (w, h): (u32, u32), factor: u32, src: &[T]) -> Vec<T>
+where
+	T: Pod + Send + Sync,
+	Format: AcceptsPixels<T>,
+{
+	let (dw, dh) = (w / factor, h / factor);
+	let src_image = vk.storage_image((w, h), format);
+	let dst_image = vk.storage_image((dw, dh), format);
+	let upload = vk.cpu_buffer_from_slice(src, BufferUsage::transfer_source());
+	let download = vk.cpu_buffer::<T>((dw * dh) as usize, BufferUsage::transfer_destination());
+
+	let mut builder = vk.auto_command_buffer_builder();
+	builder.copy_buffer_to_image(upload, src_image.clone()).unwrap();
+	Downsample::new(vk, filter).record(&mut builder, src_image, dst_image.clone());
+	builder.copy_image_to_buffer(dst_image, download.clone()).unwrap();
+	common::run(vk, builder);
+
+	let data = download.read().unwrap().to_vec();
+	data
+}
@@ -0,0 +1,244 @@
+use std::convert::TryInto;
+use std::path::PathBuf;
+use vulkan_playground::*;
+
+#[test]
+fn png_rgba8() {
+	let (w, h) = (37, 23);
+	let pixels: Vec<u8> = (0..w * h * 4).map(|i| (i * 7 % 251 + i / 200) as u8).collect();
+	let file = export("rgba8.png", ExportLayout::new(w, h, 4, SampleType::U8), &pixels, &[1, 10, 12]);
+
+	let got = image::load_from_memory(&file).unwrap().into_rgba();
+	assert_eq!(got.dimensions(), (w, h));
+	assert_eq!(got.into_raw(), pixels);
+}
+
+#[test]
+fn png_gray16() {
+	let (w, h) = (20, 30);
+	let samples: Vec<u16> = (0..w * h).map(|i| (i * 997) as u16).collect();
+	let file = export(
+		"gray16.png",
+		ExportLayout::new(w, h, 1, SampleType::U16),
+		bytemuck::cast_slice(&samples),
+		&[7, 7, 7, 9],
+	);
+
+	let got = image::load_from_memory(&file).unwrap();
+	assert_eq!(got.color(), image::ColorType::L16);
+	assert_eq!(got.as_luma16().unwrap().to_vec(), samples);
+}
+
+// The bands are compressed separately and stitched into one zlib stream, which inflates in one go, checksum included.
+#[test]
+fn png_zlib_stream() {
+	let (w, h) = (64, 40);
+	let layout = ExportLayout::new(w, h, 4, SampleType::U8);
+	let pixels: Vec<u8> = (0..w * h * 4).map(|i| (i * 13 % 241 + i / 500) as u8).collect();
+	let file = export("zlib.png", layout, &pixels, &[1, 3, 10, 1, 25]);
+
+	// the IDAT chunks, after the signature and IHDR
+	let mut stream = Vec::new();
+	let mut at = 8;
+	while at < file.len() {
+		let len = u32::from_be_bytes(file[at..at + 4].try_into().unwrap()) as usize;
+		if &file[at + 4..at + 8] == b"IDAT" {
+			stream.extend_from_slice(&file[at + 8..at + 8 + len]);
+		}
+		at += 12 + len;
+	}
+	let filtered = miniz_oxide::inflate::decompress_to_vec_zlib(&stream).unwrap();
+	// a filter type byte and the row, for each row
+	assert_eq!(filtered.len(), h as usize * (1 + layout.row_size()));
+	assert!(filtered.chunks(1 + layout.row_size()).all(|row| row[0] < 5));
+}
+
+#[test]
+fn tiff() {
+	let (w, h) = (300, 50);
+	let rgba8: Vec<u8> = (0..w * h * 4).map(|i| (i % 256) as u8).collect();
+	let file = export("rgba8.tif", ExportLayout::new(w, h, 4, SampleType::U8), &rgba8, &[3, 47]);
+	assert_eq!(image::load_from_memory(&file).unwrap().into_rgba().into_raw(), rgba8);
+
+	let gray16: Vec<u16> = (0..w * h).map(|i| (i * 31) as u16).collect();
+	let file = export(
+		"gray16.tif",
+		ExportLayout::new(w, h, 1, SampleType::U16),
+		bytemuck::cast_slice(&gray16),
+		&[h],
+	);
+	assert_eq!(image::load_from_memory(&file).unwrap().as_luma16().unwrap().to_vec(), gray16);
+
+	// The decoder has no float samples, only their bits.
+	let float: Vec<f32> = (0..w * h * 4).map(|i| i as f32 * 0.25 - 100.0).collect();
+	let file = export(
+		"rgba32f.tif",
+		ExportLayout::new(w, h, 4, SampleType::F32),
+		bytemuck::cast_slice(&float),
+		&[10, 10, 30],
+	);
+	let mut decoder = tiff::decoder::Decoder::new(std::io::Cursor::new(file)).unwrap();
+	assert_eq!(decoder.dimensions().unwrap(), (w, h));
+	assert_eq!(sample_format(&mut decoder), vec![3; 4], "IEEE floating point");
+	match decoder.read_image().unwrap() {
+		tiff::decoder::DecodingResult::U32(bits) => assert_eq!(bits, bytemuck::cast_slice::<f32, u32>(&float)),
+		_ => panic!("expected 32 bit samples"),
+	}
+
+	// half floats are 16 bit floating point samples
+	let half: Vec<u16> = (0..w * h).map(|i| [0x3c00, 0x3800, 0xbc00][i as usize % 3]).collect();
+	let file = export(
+		"gray16f.tif",
+		ExportLayout::new(w, h, 1, SampleType::F16),
+		bytemuck::cast_slice(&half),
+		&[h],
+	);
+	let mut decoder = tiff::decoder::Decoder::new(std::io::Cursor::new(file)).unwrap();
+	assert_eq!(sample_format(&mut decoder), vec![3]);
+	assert_eq!(decoder.get_tag_u32(tiff::tags::Tag::BitsPerSample).unwrap(), 16);
+	match decoder.read_image().unwrap() {
+		tiff::decoder::DecodingResult::U16(bits) => assert_eq!(bits, half),
+		_ => panic!("expected 16 bit samples"),
+	}
+}
+
+fn sample_format<R: std::io::Read + std::io::Seek>(decoder: &mut tiff::decoder::Decoder<R>) -> Vec<u16> {
+	// the SampleFormat tag, which the tiff crate does not name
+	decoder.find_tag_unsigned_vec(tiff::tags::Tag::Unknown(339)).unwrap().unwrap()
+}
+
+#[test]
+fn exr() {
+	let (w, h) = (5, 4);
+	let pixels: Vec<[f32; 4]> = (0..w * h).map(|i| [i as f32, 0.5, -1.0, 1e6]).collect();
+	let data = export(
+		"rgba32f.exr",
+		ExportLayout::new(w, h, 4, SampleType::F32),
+		bytemuck::cast_slice(&pixels),
+		&[2, 2],
+	);
+
+	assert_eq!(&data[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+	let header_end = find(&data, b"screenWindowWidth\0float\0") + 24 + 4 + 4 + 1;
+	let le_u64 = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap()) as usize;
+	let le_f32 = |at: usize| f32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+	for y in 0..h as usize {
+		let block = le_u64(header_end + 8 * y);
+		assert_eq!(&data[block..block + 4], &(y as i32).to_le_bytes());
+		// channels in alphabetical order: A, B, G, R
+		for (i, &c) in [3, 2, 1, 0].iter().enumerate() {
+			for x in 0..w as usize {
+				let got = le_f32(block + 8 + 4 * (i * w as usize + x));
+				assert_eq!(got, pixels[y * w as usize + x][c], "({}, {}) channel {}", x, y, c);
+			}
+		}
+	}
+	assert_eq!(data.len(), le_u64(header_end + 8 * (h as usize - 1)) + 8 + 16 * w as usize);
+}
+
+#[test]
+fn exr_half() {
+	let (w, h) = (3, 2);
+	// 1.0, 0.5, 2.0, -1.0 as half floats
+	let samples: Vec<u16> = (0..w * h).map(|i| [0x3c00, 0x3800, 0x4000, 0xbc00][i as usize % 4]).collect();
+	let data = export(
+		"gray16f.exr",
+		ExportLayout::new(w, h, 1, SampleType::F16),
+		bytemuck::cast_slice(&samples),
+		&[h],
+	);
+
+	let channels = find(&data, b"chlist\0") + 7 + 4;
+	assert_eq!(&data[channels..channels + 6], b"Y\0\x01\0\0\0", "one HALF channel");
+	// the last block holds the last row
+	let block = data.len() - (8 + 2 * w as usize);
+	assert_eq!(&data[block..block + 8], &[1, 0, 0, 0, 6, 0, 0, 0]);
+	let last_row: Vec<u16> = data[block + 8..].chunks(2).map(|s| u16::from_le_bytes([s[0], s[1]])).collect();
+	assert_eq!(last_row, &samples[w as usize..]);
+}
+
+#[test]
+fn layout_for_format() {
+	let layout = ExportLayout::for_format(Format::R16G16B16A16Sfloat, 4, 3).unwrap();
+	assert_eq!(layout, ExportLayout::new(4, 3, 4, SampleType::F16));
+	assert_eq!(layout.row_size(), 32);
+	assert_eq!(
+		ExportLayout::for_format(Format::R32Sfloat, 4, 3).unwrap().row_size(),
+		image_size(Format::R32Sfloat, [4, 1])
+	);
+	assert_eq!(ExportLayout::for_format(Format::R8G8B8Unorm, 4, 3), None);
+
+	assert!(FileFormat::Exr.supports(layout));
+	assert!(FileFormat::Tiff.supports(layout));
+	assert!(!FileFormat::Png.supports(layout));
+}
+
+#[test]
+fn ppm() {
+	let (w, h) = (6, 5);
+	let rgba: Vec<u8> = (0..w * h * 4).map(|i| (i * 3) as u8).collect();
+	let file = export("rgb8.ppm", ExportLayout::new(w, h, 4, SampleType::U8), &rgba, &[4, 1]);
+
+	// alpha is dropped
+	let got = image::load_from_memory(&file).unwrap().into_rgb().into_raw();
+	let want: Vec<u8> = rgba.chunks(4).flat_map(|p| p[..3].to_vec()).collect();
+	assert_eq!(got, want);
+
+	let gray16: Vec<u16> = (0..w * h).map(|i| (i * 2000) as u16).collect();
+	let file = export(
+		"gray16.pgm",
+		ExportLayout::new(w, h, 1, SampleType::U16),
+		bytemuck::cast_slice(&gray16),
+		&[5],
+	);
+	assert_eq!(image::load_from_memory(&file).unwrap().as_luma16().unwrap().to_vec(), gray16);
+}
+
+#[test]
+fn errors() {
+	let layout = ExportLayout::new(4, 2, 4, SampleType::U8);
+	assert!(matches!(Export::create(temp_path("x.gif"), layout), Err(ExportError::UnknownFormat(_))));
+	let float = ExportLayout::new(4, 2, 4, SampleType::F32);
+	assert!(matches!(
+		Export::create(temp_path("x.png"), float),
+		Err(ExportError::Unsupported(FileFormat::Png, _))
+	));
+
+	let mut export = Export::create(temp_path("rows.png"), layout).unwrap();
+	assert!(matches!(
+		export.write_rows(&[0; 15]),
+		Err(ExportError::PartialRow { len: 15, row_size: 16 })
+	));
+	assert!(matches!(
+		export.write_rows(&[0; 48]),
+		Err(ExportError::RowCount { written: 3, height: 2 })
+	));
+	export.write_rows(&[0; 16]).unwrap();
+	assert!(matches!(export.finish(), Err(ExportError::RowCount { written: 1, height: 2 })));
+	std::fs::remove_file(temp_path("rows.png")).unwrap();
+}
+
+// Export `data` to a temporary file named `name`, in bands of the given heights, and return the file's contents.
+fn export(name: &str, layout: ExportLayout, data: &[u8], bands: &[u32]) -> Vec<u8> {
+	assert_eq!(bands.iter().sum::<u32>(), layout.height);
+	let path = temp_path(name);
+	let mut export = Export::create(&path, layout).unwrap();
+	let mut start = 0;
+	for &rows in bands {
+		let end = start + rows as usize * layout.row_size();
+		export.write_rows(&data[start..end]).unwrap();
+		start = end;
+	}
+	export.finish().unwrap();
+	let file = std::fs::read(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+	file
+}
+
+fn temp_path(name: &str) -> PathBuf {
+	std::env::temp_dir().join(format!("export-{}-{}", std::process::id(), name))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> usize {
+	haystack.windows(needle.len()).position(|w| w == needle).unwrap()
+}
@@ -0,0 +1,173 @@
+#[macro_use]
+mod common;
+
+// only the output paths are tested here
+#[allow(dead_code)]
+#[path = "../src/bin/imgproc/args.rs"]
+mod args;
+
+use vulkan_playground::*;
+
+#[test]
+fn parse_steps() {
+	assert_eq!(FilterStep::parse("blur=1.5"), Ok(FilterStep::GaussianBlur { sigma: 1.5 }));
+	assert_eq!(
+		FilterStep::parse(" convolve = 1, 2, 1 "),
+		Ok(FilterStep::Convolve {
+			horizontal: vec![1.0, 2.0, 1.0],
+			vertical: vec![1.0, 2.0, 1.0],
+		})
+	);
+	assert_eq!(
+		FilterStep::parse("convolve-y=-1,0,1"),
+		Ok(FilterStep::Convolve {
+			horizontal: vec![],
+			vertical: vec![-1.0, 0.0, 1.0],
+		})
+	);
+	assert_eq!(FilterStep::parse("resize=640x0"), Ok(FilterStep::Resize { width: 640, height: 0 }));
+
+	let chain = FilterStep::parse_chain("# photo cleanup\nlinear; blur=2\n\nsobel # edges\ngray;srgb").unwrap();
+	assert_eq!(
+		chain,
+		vec![
+			FilterStep::Color(ColorConversion::SrgbToLinear),
+			FilterStep::GaussianBlur { sigma: 2.0 },
+			FilterStep::Sobel,
+			FilterStep::Color(ColorConversion::Grayscale),
+			FilterStep::Color(ColorConversion::LinearToSrgb),
+		]
+	);
+
+	for bad in &[
+		"blur",
+		"blur=-1",
+		"blur=NaN",
+		"blur=inf",
+		"blur=1e30",
+		"convolve=1,2",
+		"convolve=1,x,1",
+		"convolve=1,inf,1",
+		"convolve-x=NaN",
+		"resize=0x0",
+		"resize=10",
+		"sobel=1",
+		"sharpen",
+	] {
+		assert!(FilterStep::parse(bad).is_err(), "{:?}", bad);
+	}
+}
+
+#[test]
+fn output_paths() {
+	let dir = std::env::temp_dir().join(format!("imgproc-args-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	let input = dir.join("photo.png");
+	std::fs::write(&input, b"").unwrap();
+	let parse = |args: &[&str]| {
+		let args = args.iter().map(|a| a.to_string()).chain(vec![input.to_string_lossy().into_owned()]);
+		args::Args::parse(args).unwrap()
+	};
+	let dir_arg = dir.to_string_lossy().into_owned();
+
+	assert_eq!(parse(&[]).output_path(&input), Ok(dir.join("photo-out.png")));
+	assert_eq!(parse(&["--format", "tiff"]).output_path(&input), Ok(dir.join("photo-out.tiff")));
+	let other = std::env::temp_dir().join("elsewhere");
+	let other_arg = format!("--output-dir={}", other.display());
+	assert_eq!(parse(&[&other_arg]).output_path(&input), Ok(other.join("photo.png")));
+	assert_eq!(
+		parse(&["--output-dir", &dir_arg, "--format", "exr"]).output_path(&input),
+		Ok(dir.join("photo.exr"))
+	);
+
+	// the directory of the input, with its format, names the input itself
+	let err = parse(&["--output-dir", &dir_arg]).output_path(&input).unwrap_err();
+	assert!(err.contains("would overwrite"), "{}", err);
+	let same = dir.join(".").join("photo.png").to_string_lossy().into_owned();
+	assert!(parse(&["--output", &same]).output_path(&input).is_err());
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn argument_errors() {
+	let err = |line: &str| args::Args::parse(line.split_whitespace().map(String::from)).err().unwrap();
+	assert!(err("in.png --bogus").starts_with("unknown option \"--bogus\""));
+	assert_eq!(err("in.png --depth"), "--depth: missing value");
+	assert!(err("in.png --depth=12").starts_with("--depth: unknown depth"));
+	assert!(err("a/in.png b/in.png --output-dir out").contains("both would be written to"));
+	assert!(err("in.png in.jpg --format png").contains("both would be written to"));
+	assert!(err("in.png in.png").contains("both would be written to"));
+	assert!(args::Args::parse("a/in.png b/in.png".split_whitespace().map(String::from)).is_ok());
+}
+
+#[test]
+fn output_sizes() {
+	let resize = |width, height| FilterStep::Resize { width, height };
+	assert_eq!(resize(100, 0).output_size([400, 300]), [100, 75]);
+	assert_eq!(resize(0, 10).output_size([400, 300]), [13, 10]);
+	assert_eq!(resize(5, 7).output_size([400, 300]), [5, 7]);
+	assert_eq!(FilterStep::Sobel.output_size([400, 300]), [400, 300]);
+}
+
+#[test]
+fn gaussian_weights() {
+	let weights = gaussian_kernel(1.0);
+	assert_eq!(weights.len(), 7);
+	assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+	assert_eq!(weights[0], weights[6]);
+	assert!(weights[3] > weights[2] && weights[2] > weights[1]);
+}
+
+#[test]
+fn filters() {
+	let vk = vk_or_skip!();
+	// left half black, right half white
+	let (w, h) = (8, 4);
+	let edge = image::RgbaImage::from_fn(w, h, |x, _| if x < w / 2 { image::Rgba([0, 0, 0, 255]) } else { image::Rgba([255; 4]) });
+	let edge = DynamicImage::ImageRgba8(edge);
+
+	let run = |steps: &str| -> Vec<[f32; 4]> {
+		let chain = FilterChain::new(&vk, &FilterStep::parse_chain(steps).unwrap());
+		let output = chain.apply(&vk, &edge);
+		let data = vk.read_image_data(&output);
+		let samples: Vec<f32> = data.chunks_exact(4).map(|s| f32::from_ne_bytes([s[0], s[1], s[2], s[3]])).collect();
+		samples.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect()
+	};
+	let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+
+	// Rows only: columns stay constant, and the edge spreads by one pixel to either side.
+	let pixels = run("convolve-x=0.25,0.5,0.25");
+	let row: Vec<f32> = pixels[..w as usize].iter().map(|p| p[0]).collect();
+	assert_eq!(row, vec![0.0, 0.0, 0.0, 0.25, 0.75, 1.0, 1.0, 1.0]);
+	assert!(pixels.iter().all(|p| close(p[3], 1.0)));
+
+	// Blurring keeps the mean.
+	let pixels = run("blur=1.5");
+	let mean = pixels.iter().map(|p| p[1]).sum::<f32>() / pixels.len() as f32;
+	assert!(close(mean, 0.5), "{}", mean);
+
+	// Edges only at the boundary, with alpha kept.
+	let pixels = run("sobel");
+	for (x, p) in pixels[..w as usize].iter().enumerate() {
+		let want = if x == 3 || x == 4 { 4.0 } else { 0.0 };
+		assert!(close(p[0], want) && close(p[3], 1.0), "x {}: {:?}", x, p);
+	}
+
+	// Halving averages pairs of pixels, doubling interpolates between them.
+	let pixels = run("resize=4x2");
+	assert_eq!(pixels.len(), 8);
+	assert_eq!(pixels[..4].iter().map(|p| p[2]).collect::<Vec<_>>(), vec![0.0, 0.0, 1.0, 1.0]);
+	let pixels = run("resize=3x0");
+	assert_eq!(pixels.len(), 3 * 2);
+	assert!(close(pixels[1][0], 0.5));
+	let pixels = run("resize=16x8");
+	let row: Vec<f32> = pixels[..16].iter().map(|p| p[0]).collect();
+	assert_eq!(&row[6..10], &[0.0, 0.25, 0.75, 1.0]);
+
+	// Gray of white is white, and sRGB round trips.
+	let pixels = run("gray; linear; srgb");
+	assert!(pixels.iter().all(|p| close(p[0], p[1]) && close(p[1], p[2])));
+	assert!(close(pixels[w as usize - 1][0], 1.0));
+	vk.assert_no_validation_errors();
+}
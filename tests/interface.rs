@@ -0,0 +1,202 @@
+#[macro_use]
+mod common;
+
+use vulkan_playground::*;
+use vulkano::buffer::TypedBufferAccess;
+use vulkano::device::{DeviceExtensions, Features};
+use vulkano::format::ClearValue;
+use vulkano::image::ImageAccess;
+use vulkano::sync::GpuFuture;
+
+#[test]
+fn storage_image() {
+	let vk = vk_or_skip!();
+	let (w, h) = (16, 8);
+	let image = vk.storage_image((w, h), Format::R8G8B8A8Unorm);
+	assert_eq!(image.dimensions().width_height(), [w, h]);
+
+	let buffer = vk.image_buffer(&image);
+	assert_eq!(buffer.len(), (w * h * 4) as usize);
+	let mut builder = vk.auto_command_buffer_builder();
+	builder
+		.clear_color_image(image.clone(), ClearValue::Float([1.0, 0.0, 0.0, 1.0]))
+		.unwrap()
+		.copy_image_to_buffer(image.clone(), buffer.clone())
+		.unwrap();
+	common::run(&vk, builder);
+
+	let data = buffer.read().unwrap();
+	assert!(data.chunks(4).all(|px| px == [255, 0, 0, 255]));
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn float_storage_images() {
+	let vk = vk_or_skip!();
+	let (w, h) = (5, 3);
+	let value = [0.25, -2.0, 1e4, 1.0];
+	for &(format, channels, pixel_size) in &[
+		(Format::R32Sfloat, 1, 4),
+		(Format::R16G16B16A16Sfloat, 4, 8),
+		(Format::R32G32B32A32Sfloat, 4, 16),
+	] {
+		let image = vk.storage_image((w, h), format);
+		let buffer = vk.image_buffer(&image);
+		assert_eq!(buffer.len(), (w * h) as usize * pixel_size, "{:?}", format);
+		let mut builder = vk.auto_command_buffer_builder();
+		builder
+			.clear_color_image(image.clone(), ClearValue::Float(value))
+			.unwrap()
+			.copy_image_to_buffer(image, buffer.clone())
+			.unwrap();
+		common::run(&vk, builder);
+
+		let data = buffer.read().unwrap();
+		for pixel in data.chunks(pixel_size) {
+			for (c, sample) in pixel.chunks(pixel_size / channels).enumerate() {
+				// all test values are exact in half precision
+				let got = match sample.len() {
+					2 => half_to_f32(u16::from_ne_bytes([sample[0], sample[1]])),
+					_ => f32::from_ne_bytes([sample[0], sample[1], sample[2], sample[3]]),
+				};
+				assert_eq!(got, value[c], "{:?} channel {}", format, c);
+			}
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn read_write_image() {
+	let vk = vk_or_skip!();
+	let rgba8 = image::RgbaImage::from_fn(7, 5, |x, y| image::Rgba([x as u8 * 30, y as u8 * 50, 7, 200]));
+	let image = vk.write_image(&DynamicImage::ImageRgba8(rgba8.clone()));
+	assert_eq!(image.format(), Format::R8G8B8A8Unorm);
+	assert_eq!(vk.read_image(&image).as_rgba8(), Some(&rgba8));
+
+	// RGB gains opaque alpha
+	let rgb16 = image::ImageBuffer::from_fn(3, 4, |x, y| image::Rgb([x as u16 * 1000, y as u16 * 2000, 65535]));
+	let image = vk.write_image(&DynamicImage::ImageRgb16(rgb16.clone()));
+	assert_eq!(image.format(), Format::R16G16B16A16Unorm);
+	let got = vk.read_image(&image);
+	let got = got.as_rgba16().unwrap();
+	for (x, y, p) in rgb16.enumerate_pixels() {
+		assert_eq!(got.get_pixel(x, y).0, [p[0], p[1], p[2], 65535]);
+	}
+
+	// Floats are clamped to unsigned normalized.
+	let float = vk.storage_image((2, 2), Format::R32G32B32A32Sfloat);
+	let mut builder = vk.auto_command_buffer_builder();
+	builder
+		.clear_color_image(float.clone(), ClearValue::Float([0.5, -1.0, 2.0, 1.0]))
+		.unwrap();
+	common::run(&vk, builder);
+	let got = vk.read_image(&float);
+	assert!(got.as_rgba16().unwrap().pixels().all(|p| p.0 == [32768, 0, 65535, 65535]));
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn image_kinds() {
+	let vk = vk_or_skip!();
+	for &extent in &[Extent::d1(9), Extent::d2_array((3, 2), 4), Extent::d3((4, 3, 2)), Extent::cube(4)] {
+		let data: Vec<u8> = (0..image_size(Format::R8G8B8A8Unorm, extent)).map(|i| (i * 7) as u8).collect();
+		let image = vk.write_image_data(extent, Format::R8G8B8A8Unorm, &data);
+		assert_eq!(Extent((*image).dimensions()), extent);
+		assert_eq!(vk.read_image_data(&image), data, "{:?}", extent);
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn image_sizes() {
+	assert_eq!(image_size(Format::R8G8B8A8Unorm, [3, 2]), 24);
+	assert_eq!(image_size(Format::R16G16B16A16Sfloat, [3, 2]), 48);
+	assert_eq!(image_size(Format::R32Sfloat, [3, 2]), 24);
+	assert_eq!(image_size(Format::R32Sfloat, (3, 2, 5)), 120);
+	assert_eq!(image_size(Format::R8Unorm, Extent::d2_array([3, 2], 4)), 24);
+	assert_eq!(image_size(Format::R8Unorm, Extent::cube(2)), 24);
+	assert_eq!(image_size(Format::R8Unorm, Extent::cube_array(2, 3)), 72);
+
+	let extent = Extent::from(UVec2::new(3, 2));
+	assert_eq!(extent.dimensions(), Dimensions::Dim2d { width: 3, height: 2 });
+	assert_eq!((extent.size(), extent.layers()), (UVec3::new(3, 2, 1), 1));
+	assert_eq!(Extent::cube(4).layers(), 6);
+}
+
+#[test]
+fn cpu_accessible_buffer_from() {
+	let vk = vk_or_skip!();
+	let src = vk.cpu_accessible_buffer_from(0..=255u8);
+	assert_eq!(src.read().unwrap().len(), 256);
+
+	let dst = vk.cpu_accessible_buffer(256);
+	let mut builder = vk.auto_command_buffer_builder();
+	builder.copy_buffer(src.clone(), dst.clone()).unwrap();
+	common::run(&vk, builder);
+
+	let want: Vec<u8> = (0..=255).collect();
+	assert_eq!(&dst.read().unwrap()[..], &want[..]);
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn typed_cpu_buffer() {
+	let vk = vk_or_skip!();
+	let data: Vec<f32> = (0..100).map(|i| i as f32 * 0.5).collect();
+	let src = vk.cpu_buffer_from_slice(&data, BufferUsage::transfer_source());
+	let dst = vk.cpu_buffer::<f32>(data.len(), BufferUsage::transfer_destination());
+	assert!(dst.read().unwrap().iter().all(|&v| v == 0.0));
+
+	let mut builder = vk.auto_command_buffer_builder();
+	builder.copy_buffer(src.clone(), dst.clone()).unwrap();
+	common::run(&vk, builder);
+
+	assert_eq!(&dst.read().unwrap()[..], &data[..]);
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn device_local_round_trip() {
+	let vk = vk_or_skip!();
+	let data: Vec<u32> = (0..1000).map(|i| i * i).collect();
+	let (buffer, uploaded) = vk.upload(
+		&data,
+		BufferUsage {
+			storage_buffer: true,
+			..BufferUsage::none()
+		},
+	);
+	uploaded.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+
+	assert_eq!(vk.download(&buffer), data);
+	vk.assert_no_validation_errors();
+}
+
+// Normal half precision floats, enough for the tests.
+#[test]
+fn requirement_names() {
+	let f = Features {
+		shader_f3264: true,
+		buffer_device_address: true,
+		..Features::none()
+	};
+	assert_eq!(feature_names(&f), vec!["shader_f3264", "buffer_device_address"]);
+	assert_eq!(feature_names(&Features::all()).len(), 58);
+	assert!(feature_names(&Features::none()).is_empty());
+
+	let e = DeviceExtensions {
+		khr_swapchain: true,
+		khr_16bit_storage: true,
+		..DeviceExtensions::none()
+	};
+	assert_eq!(extension_names(&e), vec!["VK_KHR_16bit_storage", "VK_KHR_swapchain"]);
+	assert!(extension_names(&DeviceExtensions::none()).is_empty());
+}
+
+fn half_to_f32(h: u16) -> f32 {
+	let sign = if h & 0x8000 != 0 { -1.0 } else { 1.0 };
+	let exponent = ((h >> 10) & 0x1f) as i32 - 15;
+	let mantissa = 1.0 + (h & 0x3ff) as f32 / 1024.0;
+	sign * mantissa * 2f32.powi(exponent)
+}
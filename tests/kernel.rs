@@ -0,0 +1,262 @@
+#[macro_use]
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+use vulkan_playground::*;
+
+mod fill {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		src: "
+#version 450
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+layout(set = 0, binding = 0) buffer Data { uint data[]; };
+layout(push_constant) uniform PushConstants {
+	uint len;
+	uint offset;
+} pc;
+void main() {
+	uint i = gl_GlobalInvocationID.x;
+	if (i >= pc.len) {
+		return;
+	}
+	data[i] = pc.offset + i;
+}"
+	}
+}
+
+mod fill_uniform {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		src: "
+#version 450
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+layout(set = 0, binding = 0) uniform Params { uint offset; } params;
+layout(set = 0, binding = 1) buffer Data { uint data[]; };
+void main() {
+	uint i = gl_GlobalInvocationID.x;
+	if (i >= data.length()) {
+		return;
+	}
+	data[i] = params.offset + i;
+}"
+	}
+}
+
+mod fill_volume {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		src: "
+#version 450
+layout(local_size_x = 4, local_size_y = 4, local_size_z = 4, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+layout(set = 0, binding = 0, r32ui) uniform writeonly uimage3D volume;
+void main() {
+	uvec3 p = gl_GlobalInvocationID;
+	if (any(greaterThanEqual(p, uvec3(imageSize(volume))))) {
+		return;
+	}
+	imageStore(volume, ivec3(p), uvec4(p.x + 10 * p.y + 100 * p.z));
+}"
+	}
+}
+
+mod fixed_size {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		src: "
+#version 450
+layout(local_size_x = 64) in;
+layout(set = 0, binding = 0) buffer Data { uint data[]; };
+void main() {
+	data[gl_GlobalInvocationID.x] = 1;
+}"
+	}
+}
+
+mod other_constants {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		src: "
+#version 450
+layout(local_size_x = 32, local_size_y = 2, local_size_x_id = 3) in;
+layout(constant_id = 0) const uint VALUE = 7;
+layout(set = 0, binding = 0) buffer Data { uint data[]; };
+void main() {
+	data[gl_GlobalInvocationID.x] = VALUE;
+}"
+	}
+}
+
+#[test]
+fn push_constants() {
+	let vk = vk_or_skip!();
+	let shader = fill::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	assert_eq!(kernel.local_size(), [64, 1, 1]);
+
+	let buffer = vk.cpu_buffer::<u32>(1024, BufferUsage::all());
+	let mut builder = vk.auto_command_buffer_builder();
+	kernel.record_dispatch(
+		&mut builder,
+		[1000, 1, 1],
+		(buffer.clone(),),
+		fill::ty::PushConstants { len: 1000, offset: 7 },
+	);
+	common::run(&vk, builder);
+
+	let data = buffer.read().unwrap();
+	let want: Vec<u32> = (0..1024).map(|i| if i < 1000 { 7 + i } else { 0 }).collect();
+	assert_eq!(&data[..], &want[..]);
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn specialized_local_size() {
+	let vk = vk_or_skip!();
+	let shader = fill::Shader::load(vk.device()).unwrap();
+	let spec = fill::SpecializationConstants {
+		constant_0: 16,
+		..Default::default()
+	};
+	let kernel = Kernel::with_specialization(&vk, &shader.main_entry_point(), spec);
+	assert_eq!(kernel.local_size(), [16, 1, 1]);
+	assert_eq!(kernel.group_count([1000, 1, 1]), [63, 1, 1]);
+
+	let buffer = vk.cpu_buffer::<u32>(1000, BufferUsage::all());
+	let mut builder = vk.auto_command_buffer_builder();
+	kernel.record_dispatch(
+		&mut builder,
+		[1000, 1, 1],
+		(buffer.clone(),),
+		fill::ty::PushConstants { len: 1000, offset: 0 },
+	);
+	common::run(&vk, builder);
+
+	let data = buffer.read().unwrap();
+	assert!(data.iter().enumerate().all(|(i, &x)| x == i as u32));
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn volume() {
+	let vk = vk_or_skip!();
+	let shader = fill_volume::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let size = UVec3::new(5, 6, 7);
+	let image = vk.storage_image(size, Format::R32Uint);
+
+	let mut builder = vk.auto_command_buffer_builder();
+	kernel.record_dispatch(&mut builder, size.into(), (image.clone(),), ());
+	common::run(&vk, builder);
+
+	let data = vk.read_image_data(&image);
+	let got: &[u32] = bytemuck::cast_slice(&data);
+	let want: Vec<u32> = (0..7)
+		.flat_map(|z| (0..6).flat_map(move |y| (0..5).map(move |x| x + 10 * y + 100 * z)))
+		.collect();
+	assert_eq!(got, &want[..]);
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn push_constant_type_too_small() {
+	let vk = vk_or_skip!();
+	let shader = fill::Shader::load(vk.device()).unwrap();
+	let result = panic::catch_unwind(AssertUnwindSafe(|| Kernel::<()>::new(&vk, &shader.main_entry_point())));
+	assert!(result.is_err());
+}
+
+#[test]
+fn fixed_local_size() {
+	let vk = vk_or_skip!();
+	let shader = fixed_size::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	assert_eq!(kernel.local_size(), [64, 1, 1]);
+
+	let buffer = vk.cpu_buffer::<u32>(128, BufferUsage::all());
+	let mut builder = vk.auto_command_buffer_builder();
+	kernel.record_dispatch(&mut builder, [128, 1, 1], (buffer.clone(),), ());
+	common::run(&vk, builder);
+	assert!(buffer.read().unwrap().iter().all(|&x| x == 1));
+	vk.assert_no_validation_errors();
+}
+
+// Constant 0 is not part of the workgroup size, constant 3 is.
+#[test]
+fn local_size_constant_ids() {
+	let vk = vk_or_skip!();
+	let shader = other_constants::Shader::load(vk.device()).unwrap();
+	assert_eq!(Kernel::<()>::new(&vk, &shader.main_entry_point()).local_size(), [32, 2, 1]);
+	let spec = other_constants::SpecializationConstants {
+		constant_3: 8,
+		..Default::default()
+	};
+	assert_eq!(
+		Kernel::<()>::with_specialization(&vk, &shader.main_entry_point(), spec).local_size(),
+		[8, 2, 1]
+	);
+}
+
+// Hand-assembled: `layout(local_size_x = 8, local_size_y = 4, local_size_x_id = 5) in;`
+#[test]
+fn reflect_local_size() {
+	let op = |opcode: u32, operands: &[u32]| {
+		let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+		words.extend_from_slice(operands);
+		words
+	};
+	let header = vec![0x0723_0203, 0x0001_0000, 0, 100, 0];
+	let (main, uint, uvec3, x, y, z, builtin) = (1, 2, 3, 4, 5, 6, 7);
+	let words: Vec<u32> = [
+		header,
+		op(16, &[main, 17, 8, 4, 1]), // OpExecutionMode LocalSize
+		op(71, &[x, 1, 5]),           // OpDecorate SpecId 5
+		op(71, &[builtin, 11, 25]),   // OpDecorate BuiltIn WorkgroupSize
+		op(50, &[uint, x, 8]),        // OpSpecConstant
+		op(43, &[uint, y, 4]),        // OpConstant
+		op(43, &[uint, z, 1]),
+		op(51, &[uvec3, builtin, x, y, z]), // OpSpecConstantComposite
+	]
+	.concat();
+	assert_eq!(
+		LocalSize::reflect(&words),
+		Some(LocalSize {
+			size: [8, 4, 1],
+			spec_ids: [Some(5), None, None],
+		})
+	);
+
+	// without the built-in, only the execution mode
+	assert_eq!(
+		LocalSize::reflect(&words[..5 + 6]),
+		Some(LocalSize {
+			size: [8, 4, 1],
+			spec_ids: [None; 3],
+		})
+	);
+	assert_eq!(LocalSize::reflect(&words[..5]), None);
+	assert_eq!(LocalSize::reflect(&[1, 2, 3, 4, 5]), None);
+}
+
+#[test]
+fn uniform_ring() {
+	let vk = vk_or_skip!();
+	let shader = fill_uniform::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let ring = vk.uniform_ring();
+
+	// Several dispatches in flight at once, each with its own parameters.
+	let buffers: Vec<_> = (0..3).map(|_| vk.cpu_buffer::<u32>(100, BufferUsage::all())).collect();
+	let mut builder = vk.auto_command_buffer_builder();
+	for (k, buffer) in buffers.iter().enumerate() {
+		let params = ring.next(fill_uniform::ty::Params { offset: 1000 * k as u32 });
+		kernel.record_dispatch(&mut builder, [100, 1, 1], (params, buffer.clone()), ());
+	}
+	common::run(&vk, builder);
+
+	for (k, buffer) in buffers.iter().enumerate() {
+		let want: Vec<u32> = (0..100).map(|i| 1000 * k as u32 + i).collect();
+		assert_eq!(&buffer.read().unwrap()[..], &want[..]);
+	}
+	vk.assert_no_validation_errors();
+}
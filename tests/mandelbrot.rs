@@ -0,0 +1,790 @@
+#[macro_use]
+mod common;
+
+// only the parsing is tested here
+#[allow(dead_code)]
+#[path = "../src/bin/mandelbrot/args.rs"]
+mod args;
+#[path = "../src/bin/mandelbrot/color.rs"]
+mod color;
+#[path = "../src/bin/mandelbrot/fractal.rs"]
+mod fractal;
+#[path = "../src/bin/mandelbrot/output.rs"]
+mod output;
+#[path = "../src/bin/mandelbrot/palette.rs"]
+mod palette;
+#[path = "../src/bin/mandelbrot/perturbation.rs"]
+mod perturbation;
+#[path = "../src/bin/mandelbrot/tiles.rs"]
+mod tiles;
+
+use color::Colorizer;
+use fractal::Fractal;
+use output::{Output, OutputFormat};
+use palette::Palette;
+use perturbation::{check_pixel_size, reference_orbit, Fixed};
+use tiles::{tiles, Tile};
+use vulkan_playground::*;
+use vulkano::format::ClearValue;
+
+mod cs {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/mandelbrot.glsl",
+	}
+}
+
+mod cs_f64 {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/mandelbrot.glsl",
+		define: [("F64", "1")],
+	}
+}
+
+mod perturbation_f32 {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/perturbation.glsl",
+	}
+}
+
+mod perturbation_f64 {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		path: "src/bin/mandelbrot/perturbation.glsl",
+		define: [("F64", "1")],
+	}
+}
+
+// Default view of the mandelbrot binary: 3 units vertically, around the whole fractal.
+fn full_view(fractal: Fractal, h: u32) -> cs::ty::PushConstants {
+	let center = fractal.default_center();
+	let julia_c = fractal.julia_c().unwrap_or([0.0; 2]);
+	cs::ty::PushConstants {
+		center: [center[0].parse().unwrap(), center[1].parse().unwrap()],
+		julia_c: [julia_c[0] as f32, julia_c[1] as f32],
+		tile_offset: [0.0; 2],
+		pixel_size: 3.0 / h as f32,
+		iterations: 64,
+	}
+}
+
+fn specialization(fractal: Fractal) -> cs::SpecializationConstants {
+	cs::SpecializationConstants {
+		formula: fractal.formula(),
+		power: fractal.power(),
+		julia: fractal.julia_c().is_some() as u32,
+		..Default::default()
+	}
+}
+
+fn f64_interface() -> InterfaceBuilder {
+	Interface::builder().optional_features(Features {
+		shader_f3264: true,
+		..Features::none()
+	})
+}
+
+#[test]
+fn mandelbrot_matches_cpu_reference() {
+	let vk = vk_or_skip!();
+	check_against_reference(&vk, 64, 48, Fractal::Mandelbrot, full_view(Fractal::Mandelbrot, 48));
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn mandelbrot_covers_partial_workgroups() {
+	let vk = vk_or_skip!();
+	check_against_reference(&vk, 61, 45, Fractal::Mandelbrot, full_view(Fractal::Mandelbrot, 45));
+	check_against_reference(&vk, 100, 7, Fractal::Mandelbrot, full_view(Fractal::Mandelbrot, 7));
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn mandelbrot_zoomed_view() {
+	let vk = vk_or_skip!();
+	let params = cs::ty::PushConstants {
+		center: [-0.745, 0.1],
+		julia_c: [0.0; 2],
+		tile_offset: [0.0; 2],
+		pixel_size: 0.05 / 40.0,
+		iterations: 200,
+	};
+	check_against_reference(&vk, 40, 40, Fractal::Mandelbrot, params);
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn fractal_families() {
+	let vk = vk_or_skip!();
+	for &fractal in &[
+		Fractal::Julia { c: [-0.8, 0.156] },
+		Fractal::BurningShip,
+		Fractal::Tricorn,
+		Fractal::Multibrot { power: 3 },
+		Fractal::Multibrot { power: 6 },
+	] {
+		check_against_reference(&vk, 48, 40, fractal, full_view(fractal, 40));
+	}
+	assert!(Fractal::Mandelbrot.supports_perturbation() && !Fractal::Tricorn.supports_perturbation());
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn fractal_options() {
+	let args = parse("--fractal multibrot --power 4").unwrap();
+	assert_eq!(args.fractal, Fractal::Multibrot { power: 4 });
+	let args = parse("--fractal=julia --c=0.25,-0.5").unwrap();
+	assert_eq!(args.fractal, Fractal::Julia { c: [0.25, -0.5] });
+
+	// the option that does not apply is reported, whichever others are given
+	let err = |line| parse(line).err().unwrap();
+	assert!(err("--fractal julia --c 0,0 --power 4").starts_with("--power: only applies"));
+	assert!(err("--fractal julia --power 4").starts_with("--power: only applies"));
+	assert!(err("--fractal multibrot --c 0,0 --power 4").starts_with("--c: only applies"));
+	assert!(err("--c 0,0").starts_with("--c: only applies"));
+	assert!(err("--fractal tricorn --power 4").starts_with("--power: only applies"));
+	assert!(err("--fractal multibrot --power 1").starts_with("--power: must be at least 2"));
+}
+
+#[test]
+fn argument_errors() {
+	let err = |line| parse(line).err().unwrap();
+	// unknown options are reported as such, also without a value
+	assert!(err("--bogus").starts_with("unknown option \"--bogus\""));
+	assert!(err("--width 10 --bogus").starts_with("unknown option"));
+	assert!(err("--bogus=1").starts_with("unknown option"));
+	assert_eq!(err("--width"), "--width: missing value");
+	assert_eq!(parse("--width=10 --height 20").map(|a| (a.width, a.height)), Ok((10, 20)));
+
+	// the supersampled size is a u32
+	assert!(err("--width 70000 --supersample 70000").starts_with("--supersample:"));
+	assert!(err("--height 4000000000 --supersample 2").starts_with("--supersample:"));
+	let args = parse("--width 65536 --height 2 --supersample 65535").unwrap();
+	assert_eq!(args.render_size(), (65536 * 65535, 2 * 65535));
+}
+
+// At this pixel size float can no longer tell neighbouring pixels apart, double can.
+#[test]
+fn mandelbrot_f64() {
+	let vk = vk_or_skip!(f64_interface());
+	if !vk.enabled_features().shader_f3264 {
+		eprintln!("skipping test: no shaderFloat64");
+		return;
+	}
+	let (w, h) = (32, 32);
+	let (center, pixel_size, iterations) = ([-0.743643887037, 0.131825904205], 1e-10, 500);
+
+	let shader = cs_f64::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let params = cs_f64::ty::PushConstants {
+		center,
+		julia_c: [0.0; 2],
+		tile_offset: [0.0; 2],
+		pixel_size,
+		iterations,
+	};
+	let got = render(&vk, w, h, |builder, image| kernel.record_dispatch(builder, [w, h, 1], (image,), params));
+	check(&got, w, h, |x, y| {
+		let c = [center[0] + offset(x, w) * pixel_size, center[1] + offset(y, h) * pixel_size];
+		escape_time(c, iterations)
+	});
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn perturbation_matches_direct_iteration() {
+	let vk = vk_or_skip!();
+	let (w, h) = (40, 40);
+	let center = ["-0.745".to_string(), "0.1".to_string()];
+	let (pixel_size, iterations) = (0.05 / 40.0, 200);
+	let orbit: Vec<[f32; 2]> = reference_orbit(&center, pixel_size, iterations)
+		.unwrap()
+		.iter()
+		.map(|z| [z[0] as f32, z[1] as f32])
+		.collect();
+
+	let shader = perturbation_f32::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let orbit = vk.cpu_buffer_from_slice(&orbit, BufferUsage::all());
+	let params = perturbation_f32::ty::PushConstants {
+		tile_offset: [0.0; 2],
+		pixel_size: pixel_size as f32,
+		iterations,
+	};
+	let got = render(&vk, w, h, |builder, image| {
+		kernel.record_dispatch(builder, [w, h, 1], (image, orbit.clone()), params)
+	});
+	check(&got, w, h, |x, y| {
+		let c = [-0.745 + offset(x, w) * pixel_size, 0.1 + offset(y, h) * pixel_size];
+		escape_time(c, iterations)
+	});
+	vk.assert_no_validation_errors();
+}
+
+// Beyond double precision: compare against iterating each pixel in fixed point.
+#[test]
+fn perturbation_deep_zoom() {
+	let vk = vk_or_skip!(f64_interface());
+	if !vk.enabled_features().shader_f3264 {
+		eprintln!("skipping test: no shaderFloat64");
+		return;
+	}
+	let shader = perturbation_f64::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let seahorse = [
+		"-0.743643887037158704752191506114774".to_string(),
+		"0.131825904205311970493132056385139".to_string(),
+	];
+	// c = i is on the boundary of the set at every scale, with escape times growing only with the log of the zoom
+	let misiurewicz = ["0".to_string(), "1".to_string()];
+	for &(center, pixel_size, iterations) in &[(&seahorse, 4e-20, 12000), (&misiurewicz, 1e-30, 1000)] {
+		let (w, h) = (12, 12);
+		let orbit = reference_orbit(center, pixel_size, iterations).unwrap();
+		let orbit = vk.cpu_buffer_from_slice(&orbit, BufferUsage::all());
+		let params = perturbation_f64::ty::PushConstants {
+			tile_offset: [0.0; 2],
+			pixel_size,
+			iterations,
+		};
+		let got = render(&vk, w, h, |builder, image| {
+			kernel.record_dispatch(builder, [w, h, 1], (image, orbit.clone()), params)
+		});
+		check(&got, w, h, |x, y| {
+			fixed_escape_time(center, [offset(x, w), offset(y, h)], pixel_size, iterations)
+		});
+	}
+	vk.assert_no_validation_errors();
+}
+
+// Float deltas resolve 1e-30 too, with the orbit rounded to float.
+#[test]
+fn perturbation_f32_deep_zoom() {
+	let vk = vk_or_skip!();
+	let (w, h) = (12, 12);
+	let center = ["0".to_string(), "1".to_string()];
+	let (pixel_size, iterations) = (1e-30, 1000);
+	let orbit: Vec<[f32; 2]> = reference_orbit(&center, pixel_size, iterations)
+		.unwrap()
+		.iter()
+		.map(|z| [z[0] as f32, z[1] as f32])
+		.collect();
+
+	let shader = perturbation_f32::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let orbit = vk.cpu_buffer_from_slice(&orbit, BufferUsage::all());
+	let params = perturbation_f32::ty::PushConstants {
+		tile_offset: [0.0; 2],
+		pixel_size: pixel_size as f32,
+		iterations,
+	};
+	let got = render(&vk, w, h, |builder, image| {
+		kernel.record_dispatch(builder, [w, h, 1], (image, orbit.clone()), params)
+	});
+	check(&got, w, h, |x, y| {
+		fixed_escape_time(&center, [offset(x, w), offset(y, h)], pixel_size, iterations)
+	});
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn perturbation_range() {
+	assert!(check_pixel_size(1e-30, false).is_ok());
+	assert!(check_pixel_size(1e-30, true).is_ok());
+	assert!(check_pixel_size(1e-40, true).is_ok());
+	// float deltas of a pixel or two would be subnormal
+	let err = check_pixel_size(1e-40, false).unwrap_err();
+	assert!(err.contains("float"), "{}", err);
+	assert!(check_pixel_size(1e-305, true).is_err());
+}
+
+#[test]
+fn fixed_point_arithmetic() {
+	for s in &[
+		"0",
+		"-0",
+		"7.",
+		".25",
+		"-1.5e-3",
+		"2e1",
+		"123456.789",
+		"-0.743643887037158704752191506114774",
+	] {
+		let want: f64 = s.parse().unwrap();
+		let got = Fixed::parse(s, 4).unwrap().to_f64();
+		assert!((got - want).abs() <= want.abs() * 1e-15, "{}: {}", s, got);
+	}
+	for s in &["", "-", "1.2.3", "abc", "1e", "5000000000"] {
+		assert!(Fixed::parse(s, 4).is_none(), "{:?}", s);
+	}
+
+	let values = [-1.75, -0.3, 0.0, 0.2, 1.9];
+	for &a in &values {
+		for &b in &values {
+			let (fa, fb) = (Fixed::parse(&a.to_string(), 3).unwrap(), Fixed::parse(&b.to_string(), 3).unwrap());
+			assert!((fa.add(&fb).to_f64() - (a + b)).abs() < 1e-15, "{} + {}", a, b);
+			assert!((fa.sub(&fb).to_f64() - (a - b)).abs() < 1e-15, "{} - {}", a, b);
+			assert!((fa.mul(&fb).to_f64() - (a * b)).abs() < 1e-15, "{} * {}", a, b);
+		}
+	}
+
+	// the integer part is 32 bits
+	let big = Fixed::parse("70000", 2).unwrap();
+	assert_eq!(big.mul(&Fixed::parse("-60000", 2).unwrap()).to_f64(), -4.2e9);
+	assert!(std::panic::catch_unwind(|| big.mul(&big)).is_err());
+
+	// 1e-40 is lost in f64, but not in 192 fraction bits.
+	let x = Fixed::parse("1.0000000000000000000000000000000000000001", 6).unwrap();
+	let diff = x.sub(&Fixed::parse("1", 6).unwrap()).to_f64();
+	assert!((diff - 1e-40).abs() < 1e-55, "{}", diff);
+}
+
+#[test]
+fn reference_orbit_matches_f64() {
+	let orbit = reference_orbit(&["-0.1".to_string(), "0.65".to_string()], 1e-3, 100).unwrap();
+	let mut z = [0.0f64, 0.0];
+	for got in &orbit {
+		assert!((got[0] - z[0]).abs() < 1e-9 && (got[1] - z[1]).abs() < 1e-9, "{:?} != {:?}", got, z);
+		z = [z[0] * z[0] - z[1] * z[1] - 0.1, 2.0 * z[0] * z[1] + 0.65];
+	}
+	// escapes before the iteration limit
+	assert!(orbit.len() < 101);
+	let last = orbit.last().unwrap();
+	assert!(last[0] * last[0] + last[1] * last[1] > 16.0);
+}
+
+// Tiles render exactly the same pixels as the whole image at once.
+#[test]
+fn tiled_rendering() {
+	let vk = vk_or_skip!();
+	let (w, h) = (50, 37);
+	let shader = cs::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let params = full_view(Fractal::Mandelbrot, h);
+	let whole = render(&vk, w, h, |builder, image| kernel.record_dispatch(builder, [w, h, 1], (image,), params));
+
+	for tile in tiles(w, h, 16).iter().flatten() {
+		let offset = tile.offset((w, h), 1);
+		let params = cs::ty::PushConstants {
+			tile_offset: [offset[0] as f32, offset[1] as f32],
+			..params
+		};
+		let got = render(&vk, tile.width, tile.height, |builder, image| {
+			kernel.record_dispatch(builder, [tile.width, tile.height, 1], (image,), params)
+		});
+		for y in 0..tile.height {
+			for x in 0..tile.width {
+				let want = whole[((tile.y + y) * w + tile.x + x) as usize];
+				assert_eq!(got[(y * tile.width + x) as usize], want, "{:?} pixel ({}, {})", tile, x, y);
+			}
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+// With a margin of 3 pixels from the neighbouring tiles, Lanczos filtered tiles match the filtered whole image.
+#[test]
+fn lanczos_tiles() {
+	let vk = vk_or_skip!();
+	let downsample = Downsample::new(&vk, DownsampleFilter::Lanczos3);
+	let (w, h, ss) = (20, 14, 2);
+	let (sw, sh) = (w * ss, h * ss);
+	let samples: Vec<f32> = (0..sw * sh).map(|i| ((i * 7919) % 101) as f32 / 100.0).collect();
+	let filter = |area: &Tile| -> Vec<f32> {
+		let region: Vec<f32> = (0..area.height * ss)
+			.flat_map(|y| (0..area.width * ss).map(move |x| ((area.y * ss + y) * sw + area.x * ss + x) as usize))
+			.map(|i| samples[i])
+			.collect();
+		let src = vk.write_image_data((area.width * ss, area.height * ss), Format::R32Sfloat, bytemuck::cast_slice(&region));
+		let dst = vk.storage_image((area.width, area.height), Format::R32Sfloat);
+		let mut builder = vk.auto_command_buffer_builder();
+		downsample.record(&mut builder, src, dst.clone());
+		common::run(&vk, builder);
+		let data = vk.read_image_data(&dst);
+		data.chunks_exact(4).map(|s| f32::from_ne_bytes([s[0], s[1], s[2], s[3]])).collect()
+	};
+
+	let whole = filter(&Tile {
+		x: 0,
+		y: 0,
+		width: w,
+		height: h,
+	});
+	for tile in tiles(w, h, 8).iter().flatten() {
+		let area = tile.expand(3, (w, h));
+		let got = filter(&area);
+		for y in 0..tile.height {
+			for x in 0..tile.width {
+				let got = got[((tile.y - area.y + y) * area.width + tile.x - area.x + x) as usize];
+				let want = whole[((tile.y + y) * w + tile.x + x) as usize];
+				assert!((got - want).abs() < 1e-5, "{:?} pixel ({}, {}): {} != {}", tile, x, y, got, want);
+			}
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn tile_layout() {
+	let rows = tiles(50, 37, 16);
+	assert_eq!(rows.len(), 3);
+	assert!(rows.iter().all(|row| row.len() == 4));
+	assert_eq!(
+		rows[0][0],
+		Tile {
+			x: 0,
+			y: 0,
+			width: 16,
+			height: 16
+		}
+	);
+	assert_eq!(
+		rows[2][3],
+		Tile {
+			x: 48,
+			y: 32,
+			width: 2,
+			height: 5
+		}
+	);
+	// every pixel exactly once
+	let area: u32 = rows.iter().flatten().map(|t| t.width * t.height).sum();
+	assert_eq!(area, 50 * 37);
+
+	assert_eq!(
+		tiles(8, 8, 100),
+		vec![vec![Tile {
+			x: 0,
+			y: 0,
+			width: 8,
+			height: 8
+		}]]
+	);
+	assert_eq!(rows[0][0].offset((50, 37), 1), [-17.0, -10.5]);
+	assert_eq!(rows[2][3].offset((50, 37), 2), [2.0 * 24.0, 2.0 * 16.0]);
+
+	// margins stop at the image edges
+	assert_eq!(
+		rows[1][1].expand(3, (50, 37)),
+		Tile {
+			x: 13,
+			y: 13,
+			width: 22,
+			height: 22
+		}
+	);
+	assert_eq!(
+		rows[2][3].expand(3, (50, 37)),
+		Tile {
+			x: 45,
+			y: 29,
+			width: 5,
+			height: 8
+		}
+	);
+	assert_eq!(rows[0][0].expand(3, (50, 37)).offset((50, 37), 1), [-15.5, -9.0]);
+}
+
+// Rows written band by band read back as the same image.
+#[test]
+fn output_in_bands() {
+	let (w, h) = (7, 5);
+	let pixels: Vec<u8> = (0..w * h * 4).map(|i| (i * 37 % 256) as u8).collect();
+	for ext in &["png", "tif", "bmp"] {
+		let path = std::env::temp_dir().join(format!("mandelbrot-output-{}.{}", std::process::id(), ext));
+		let format = OutputFormat::from_path(&path).unwrap();
+		let mut output = Output::create(&path, format, Format::R8G8B8A8Unorm, w, h).unwrap();
+		let row = 4 * w as usize;
+		output.write_rows(&pixels[..2 * row]).unwrap();
+		output.write_rows(&pixels[2 * row..]).unwrap();
+		output.finish().unwrap();
+
+		let got = image::open(&path).unwrap().to_rgba();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(got.dimensions(), (w, h));
+		assert_eq!(got.into_raw(), pixels, "{:?}", format);
+	}
+}
+
+#[test]
+fn colorize_linear() {
+	let vk = vk_or_skip!();
+	let (values, colors, _) = render_colored(&vk, 64, 48, &Palette::builtin("grey").unwrap(), false);
+	for (&v, &c) in values.iter().zip(&colors) {
+		if v >= 64.0 {
+			assert_eq!(c, [0, 0, 0, 255]);
+		} else {
+			let want = (v / 64.0 * 255.0).round();
+			assert!((c[0] as f32 - want).abs() <= 1.0, "count {}: {:?}", v, c);
+			assert!(c[0] == c[1] && c[1] == c[2] && c[3] == 255, "count {}: {:?}", v, c);
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+// Float outputs hold the same colors as linear intensities.
+#[test]
+fn colorize_float() {
+	let vk = vk_or_skip!();
+	let (w, h) = (64, 48);
+	let palette = Palette::builtin("ultra").unwrap();
+	let (values, colors, _) = render_colored(&vk, w, h, &palette, false);
+
+	let colorizer = Colorizer::new(&vk, &palette);
+	let values_image = vk.storage_image((w, h), Format::R32Sfloat);
+	let output = vk.storage_image((w, h), Format::R32G32B32A32Sfloat);
+	let upload = vk.cpu_buffer_from_slice(&values, BufferUsage::transfer_source());
+	let download = vk.cpu_buffer::<[f32; 4]>((w * h) as usize, BufferUsage::transfer_destination());
+	let mut builder = vk.auto_command_buffer_builder();
+	builder.copy_buffer_to_image(upload, values_image.clone()).unwrap();
+	colorizer.record_colorize(&mut builder, values_image, output.clone(), 64, None);
+	builder.copy_image_to_buffer(output, download.clone()).unwrap();
+	common::run(&vk, builder);
+
+	let srgb = |v: f32| if v <= 0.0031308 { 12.92 * v } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+	for (got, want) in download.read().unwrap().iter().zip(&colors) {
+		for c in 0..3 {
+			assert!((srgb(got[c]) * 255.0 - want[c] as f32).abs() <= 1.0, "{:?} != {:?}", got, want);
+		}
+		assert_eq!(got[3], 1.0);
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn histogram_equalization() {
+	let vk = vk_or_skip!();
+	let (values, colors, cdf) = render_colored(&vk, 64, 48, &Palette::builtin("grey").unwrap(), true);
+
+	let mut want = vec![0; 64];
+	for &v in values.iter().filter(|&&v| v < 64.0) {
+		want[v as usize] += 1;
+	}
+	for i in 1..want.len() {
+		want[i] += want[i - 1];
+	}
+	assert_eq!(cdf, want);
+
+	// Equalized, the escaped pixels spread evenly over the grey levels.
+	let escaped: Vec<f32> = values
+		.iter()
+		.zip(&colors)
+		.filter(|(&v, _)| v < 64.0)
+		.map(|(_, c)| c[0] as f32 / 255.0)
+		.collect();
+	let mean = escaped.iter().sum::<f32>() / escaped.len() as f32;
+	assert!((mean - 0.5).abs() < 0.1, "mean {}", mean);
+	for (&v, &c) in values.iter().zip(&colors) {
+		if v >= 64.0 {
+			assert_eq!(c, [0, 0, 0, 255]);
+		}
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn palette_files() {
+	for name in palette::BUILTIN {
+		Palette::load(name).unwrap();
+	}
+	assert!(Palette::load("no-such-palette").is_err());
+
+	let p = Palette::parse("# comment\n\n1 #ffffff\n0 #000000\n 0.5  #FF0000 \n").unwrap();
+	assert_eq!(p.color(0.25), [0.5, 0.0, 0.0]);
+	assert_eq!(p.color(-1.0), [0.0; 3]);
+	assert_eq!(p.color(2.0), [1.0; 3]);
+	assert_eq!(p.lut(3), vec![[0.0, 0.0, 0.0, 1.0], [1.0, 0.0, 0.0, 1.0], [1.0, 1.0, 1.0, 1.0]]);
+
+	for text in &[
+		"",
+		"# only a comment",
+		"0.5",
+		"0.5 #ff00",
+		"1.5 #ffffff",
+		"x #ffffff",
+		"0 ffffff",
+		"0 #ffffff extra",
+	] {
+		assert!(Palette::parse(text).is_err(), "{:?}", text);
+	}
+
+	let path = std::env::temp_dir().join(format!("mandelbrot-palette-{}.txt", std::process::id()));
+	std::fs::write(&path, "0 #102030\n1 #405060\n").unwrap();
+	let loaded = Palette::load(path.to_str().unwrap());
+	std::fs::remove_file(&path).unwrap();
+	assert_eq!(loaded.unwrap(), Palette::parse("0 #102030\n1 #405060").unwrap());
+}
+
+// Render a w x h image and compare every pixel with the CPU reference.
+fn parse(line: &str) -> Result<args::Args, String> {
+	args::Args::parse(line.split_whitespace().map(String::from))
+}
+
+fn check_against_reference(vk: &Interface, w: u32, h: u32, fractal: Fractal, params: cs::ty::PushConstants) {
+	let shader = cs::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::with_specialization(vk, &shader.main_entry_point(), specialization(fractal));
+	assert_eq!(kernel.local_size(), [8, 8, 1]);
+	assert_eq!(kernel.group_count([w, h, 1]), [w.div_ceil(8), h.div_ceil(8), 1]);
+
+	let got = render(vk, w, h, |builder, image| kernel.record_dispatch(builder, [w, h, 1], (image,), params));
+	check(&got, w, h, |x, y| reference(x, y, w, h, fractal, &params));
+}
+
+// Record commands writing smooth iteration counts into a w x h image and return them.
+fn render<F>(vk: &Interface, w: u32, h: u32, record: F) -> Vec<f32>
+where
+	F: FnOnce(&mut AutoCommandBufferBuilder, Arc<StorageImage<Format>>),
+{
+	let values = vk.storage_image((w, h), Format::R32Sfloat);
+	let cpu_buffer = vk.cpu_buffer::<f32>((w * h) as usize, BufferUsage::transfer_destination());
+	let mut builder = vk.auto_command_buffer_builder();
+	builder.clear_color_image(values.clone(), ClearValue::Float([-1.0; 4])).unwrap();
+	record(&mut builder, values.clone());
+	builder.copy_image_to_buffer(values, cpu_buffer.clone()).unwrap();
+	common::run(vk, builder);
+
+	let data = cpu_buffer.read().unwrap().to_vec();
+	// Pixels that were not written keep the clear value.
+	assert!(data.iter().all(|&v| v >= 0.0), "{}x{}: incomplete image", w, h);
+	data
+}
+
+// Render the full view and color it.
+// Returns the smooth counts, the colors and, when equalizing, the cumulative histogram.
+fn render_colored(vk: &Interface, w: u32, h: u32, palette: &Palette, equalize: bool) -> (Vec<f32>, Vec<[u8; 4]>, Vec<u32>) {
+	let params = full_view(Fractal::Mandelbrot, h);
+	let shader = cs::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(vk, &shader.main_entry_point());
+	let colorizer = Colorizer::new(vk, palette);
+
+	let values = vk.storage_image((w, h), Format::R32Sfloat);
+	let output = vk.storage_image((w, h), Format::R8G8B8A8Unorm);
+	let values_buffer = vk.cpu_buffer::<f32>((w * h) as usize, BufferUsage::transfer_destination());
+	let colors_buffer = vk.cpu_buffer::<[u8; 4]>((w * h) as usize, BufferUsage::transfer_destination());
+	let mut builder = vk.auto_command_buffer_builder();
+	kernel.record_dispatch(&mut builder, [w, h, 1], (values.clone(),), params);
+	let cdf = if equalize {
+		let histogram = colorizer.histogram(vk, &mut builder, params.iterations);
+		colorizer.record_histogram(vk, &mut builder, values.clone(), histogram.clone());
+		Some(colorizer.record_cumulative(vk, &mut builder, histogram))
+	} else {
+		None
+	};
+	colorizer.record_colorize(&mut builder, values.clone(), output.clone(), params.iterations, cdf.clone());
+	builder.copy_image_to_buffer(values, values_buffer.clone()).unwrap();
+	builder.copy_image_to_buffer(output, colors_buffer.clone()).unwrap();
+	common::run(vk, builder);
+
+	let cdf = cdf.map(|cdf| vk.download(&cdf)).unwrap_or_default();
+	let values = values_buffer.read().unwrap().to_vec();
+	let colors = colors_buffer.read().unwrap().to_vec();
+	(values, colors, cdf)
+}
+
+// Compare the escape iterations, the integer parts of the smooth counts, with `want(x, y)`.
+fn check(got: &[f32], w: u32, h: u32, want: impl Fn(u32, u32) -> u32) {
+	let mut mismatches = 0;
+	for y in 0..h {
+		for x in 0..w {
+			let got = got[(y * w + x) as usize];
+			let want = want(x, y) as f32;
+			if (got.floor() - want).abs() > 1.0 {
+				mismatches += 1;
+			}
+		}
+	}
+	// Points close to the set boundary may escape one iteration earlier or later due to rounding.
+	assert!(mismatches <= w * h / 100, "{}x{}: {} pixels differ from CPU reference", w, h, mismatches);
+}
+
+// Direct iteration in fixed point, the iteration count at which |z| > 4 for the point `offset` pixels from `center`.
+fn fixed_escape_time(center: &[String; 2], offset: [f64; 2], pixel_size: f64, iterations: u32) -> u32 {
+	let frac = 6;
+	let coordinate = |center: &String, offset: f64| {
+		let delta = Fixed::parse(&format!("{:e}", offset * pixel_size), frac).unwrap();
+		Fixed::parse(center, frac).unwrap().add(&delta)
+	};
+	let (cx, cy) = (coordinate(&center[0], offset[0]), coordinate(&center[1], offset[1]));
+	let (mut zx, mut zy) = (Fixed::zero(frac), Fixed::zero(frac));
+	let mut i = 0;
+	while i < iterations {
+		let xy = zx.mul(&zy);
+		zx = zx.mul(&zx).sub(&zy.mul(&zy)).add(&cx);
+		zy = xy.add(&xy).add(&cy);
+		let (x, y) = (zx.to_f64(), zy.to_f64());
+		if x * x + y * y > 16.0 {
+			break;
+		}
+		i += 1;
+	}
+	i
+}
+
+// Offset of pixel center x from the image center, in pixels.
+fn offset(x: u32, w: u32) -> f64 {
+	x as f64 + 0.5 - w as f64 / 2.0
+}
+
+// Direct iteration in f64, the iteration count at which |z| > 4.
+fn escape_time(c: [f64; 2], iterations: u32) -> u32 {
+	let mut z = [0.0f64, 0.0];
+	let mut i = 0;
+	while i < iterations {
+		z = [z[0] * z[0] - z[1] * z[1] + c[0], 2.0 * z[0] * z[1] + c[1]];
+		if z[0] * z[0] + z[1] * z[1] > 16.0 {
+			break;
+		}
+		i += 1;
+	}
+	i
+}
+
+// CPU port of mandelbrot.glsl: escape iteration at pixel (x, y).
+fn reference(x: u32, y: u32, w: u32, h: u32, fractal: Fractal, params: &cs::ty::PushConstants) -> u32 {
+	let offset = [x as f32 + 0.5 - w as f32 / 2.0, y as f32 + 0.5 - h as f32 / 2.0];
+	let point = [
+		params.center[0] + offset[0] * params.pixel_size,
+		params.center[1] + offset[1] * params.pixel_size,
+	];
+	let (mut z, c) = match fractal {
+		Fractal::Julia { .. } => (point, params.julia_c),
+		_ => ([0.0f32, 0.0], point),
+	};
+	let mut i = 0;
+	while i < params.iterations {
+		z = match fractal {
+			Fractal::Multibrot { power } => {
+				let p = cpow(z, power);
+				[p[0] + c[0], p[1] + c[1]]
+			}
+			_ => {
+				let z = match fractal {
+					Fractal::BurningShip => [z[0].abs(), z[1].abs()],
+					Fractal::Tricorn => [z[0], -z[1]],
+					_ => z,
+				};
+				[z[0] * z[0] - z[1] * z[1] + c[0], z[1] * z[0] + z[0] * z[1] + c[1]]
+			}
+		};
+		if (z[0] * z[0] + z[1] * z[1]).sqrt() > 4.0 {
+			break;
+		}
+		i += 1;
+	}
+	i
+}
+
+// z^n by repeated squaring, as in mandelbrot.glsl.
+fn cpow(mut z: [f32; 2], mut n: u32) -> [f32; 2] {
+	let cmul = |a: [f32; 2], b: [f32; 2]| [a[0] * b[0] - a[1] * b[1], a[0] * b[1] + a[1] * b[0]];
+	let mut result = [1.0, 0.0];
+	while n > 0 {
+		if n & 1 != 0 {
+			result = cmul(result, z);
+		}
+		z = cmul(z, z);
+		n >>= 1;
+	}
+	result
+}
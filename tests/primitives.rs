@@ -0,0 +1,189 @@
+#[macro_use]
+mod common;
+
+use std::panic::AssertUnwindSafe;
+use vulkan_playground::*;
+use vulkano::buffer::BufferUsage;
+use vulkano::sync::GpuFuture;
+
+// Deterministic pseudo-random integers in [lo, hi), by xorshift.
+fn random(len: usize, lo: i32, hi: i32) -> Vec<i32> {
+	let mut state = 0x2545_f491u32;
+	(0..len)
+		.map(|_| {
+			state ^= state << 13;
+			state ^= state >> 17;
+			state ^= state << 5;
+			lo + (state % (hi - lo) as u32) as i32
+		})
+		.collect()
+}
+
+// Lengths around the block sizes, and long enough for a scan of three levels.
+const LENGTHS: &[usize] = &[1, 7, 1023, 1024, 1025, 2048, 2049, 100_000, 1_100_000];
+
+#[test]
+fn identities() {
+	assert_eq!(ReduceOp::Sum.identity::<i32>(), 0);
+	assert_eq!(ReduceOp::Min.identity::<u32>(), u32::MAX);
+	assert_eq!(ReduceOp::Max.identity::<f32>(), f32::NEG_INFINITY);
+}
+
+#[test]
+fn reductions() {
+	let vk = vk_or_skip!();
+	let sum = (
+		Reduce::new(&vk, ReduceOp::Sum),
+		Reduce::new(&vk, ReduceOp::Sum),
+		Reduce::new(&vk, ReduceOp::Sum),
+	);
+	let min = (
+		Reduce::new(&vk, ReduceOp::Min),
+		Reduce::new(&vk, ReduceOp::Min),
+		Reduce::new(&vk, ReduceOp::Min),
+	);
+	let max = (
+		Reduce::new(&vk, ReduceOp::Max),
+		Reduce::new(&vk, ReduceOp::Max),
+		Reduce::new(&vk, ReduceOp::Max),
+	);
+
+	for &len in LENGTHS {
+		let ints = random(len, -1000, 1000);
+		let uints: Vec<u32> = ints.iter().map(|&v| (v + 1000) as u32).collect();
+		// small whole numbers, which sum exactly in any order
+		let floats: Vec<f32> = random(len, -8, 8).iter().map(|&v| v as f32).collect();
+
+		assert_eq!(sum.0.apply(&vk, &ints), ints.iter().sum::<i32>(), "len {}", len);
+		assert_eq!(sum.1.apply(&vk, &uints), uints.iter().sum::<u32>(), "len {}", len);
+		assert_eq!(sum.2.apply(&vk, &floats), floats.iter().sum::<f32>(), "len {}", len);
+		assert_eq!(min.0.apply(&vk, &ints), *ints.iter().min().unwrap(), "len {}", len);
+		assert_eq!(min.1.apply(&vk, &uints), *uints.iter().min().unwrap(), "len {}", len);
+		assert_eq!(
+			min.2.apply(&vk, &floats),
+			floats.iter().copied().fold(f32::INFINITY, f32::min),
+			"len {}",
+			len
+		);
+		assert_eq!(max.0.apply(&vk, &ints), *ints.iter().max().unwrap(), "len {}", len);
+		assert_eq!(max.1.apply(&vk, &uints), *uints.iter().max().unwrap(), "len {}", len);
+		assert_eq!(
+			max.2.apply(&vk, &floats),
+			floats.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+			"len {}",
+			len
+		);
+	}
+
+	// extremes survive, and the padding of partial blocks does not leak into the result
+	assert_eq!(min.0.apply(&vk, &[5, i32::MIN, 7]), i32::MIN);
+	assert_eq!(max.1.apply(&vk, &[u32::MAX, 0]), u32::MAX);
+	assert_eq!(max.2.apply(&vk, &[-3.0, -2.0]), -2.0);
+	assert_eq!(sum.0.apply(&vk, &[]), 0);
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn scans() {
+	let vk = vk_or_skip!();
+	let ints = Scan::<i32>::new(&vk);
+	let floats = Scan::<f32>::new(&vk);
+
+	assert_eq!(ints.apply(&vk, &[3, 1, 4, 1, 5], ScanKind::Inclusive), vec![3, 4, 8, 9, 14]);
+	assert_eq!(ints.apply(&vk, &[3, 1, 4, 1, 5], ScanKind::Exclusive), vec![0, 3, 4, 8, 9]);
+	assert_eq!(ints.apply(&vk, &[], ScanKind::Inclusive), vec![]);
+
+	for &len in LENGTHS {
+		let data = random(len, -100, 100);
+		let inclusive: Vec<i32> = data
+			.iter()
+			.scan(0, |sum, &v| {
+				*sum += v;
+				Some(*sum)
+			})
+			.collect();
+		let exclusive: Vec<i32> = data.iter().scan(0, |sum, &v| Some(std::mem::replace(sum, *sum + v))).collect();
+		assert_eq!(ints.apply(&vk, &data, ScanKind::Inclusive), inclusive, "len {}", len);
+		assert_eq!(ints.apply(&vk, &data, ScanKind::Exclusive), exclusive, "len {}", len);
+
+		let data: Vec<f32> = random(len, -8, 8).iter().map(|&v| v as f32).collect();
+		let inclusive: Vec<f32> = data
+			.iter()
+			.scan(0.0, |sum, &v| {
+				*sum += v;
+				Some(*sum)
+			})
+			.collect();
+		assert_eq!(floats.apply(&vk, &data, ScanKind::Inclusive), inclusive, "len {}", len);
+	}
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn histograms() {
+	let vk = vk_or_skip!();
+	let data: Vec<u32> = random(100_000, 0, 300).iter().map(|&v| v as u32).collect();
+	let counts = Histogram::new(&vk).apply(&vk, &data, 0..256, 16);
+	let mut want = vec![0; 16];
+	for &v in data.iter().filter(|&&v| v < 256) {
+		want[v as usize / 16] += 1;
+	}
+	assert_eq!(counts, want);
+
+	// bins of 1/8 over [-1, 1): NaN and values out of range are dropped
+	let data: Vec<f32> = random(10_000, -80, 80)
+		.iter()
+		.map(|&v| v as f32 / 64.0)
+		.chain(vec![f32::NAN, 1.0])
+		.collect();
+	let counts = Histogram::new(&vk).apply(&vk, &data, -1.0..1.0, 16);
+	let mut want = vec![0; 16];
+	for &v in data.iter().filter(|v| (-1.0..1.0).contains(*v)) {
+		want[((v + 1.0) * 8.0) as usize] += 1;
+	}
+	assert_eq!(counts, want);
+
+	let counts = Histogram::new(&vk).apply(&vk, &[-5, -1, 0, 4, 5], -5..5, 2);
+	assert_eq!(counts, vec![2, 2]);
+
+	// more elements than invocations in the largest dispatch
+	let len = 256 * 65535 + 1000;
+	let data: Vec<u32> = (0..len).map(|i| i % 4).collect();
+	let counts = Histogram::new(&vk).apply(&vk, &data, 0..4, 4);
+	assert_eq!(counts, vec![len / 4; 4]);
+
+	let no_bins = std::panic::catch_unwind(AssertUnwindSafe(|| Histogram::new(&vk).apply(&vk, &[1u32], 0..4, 0)));
+	assert!(no_bins.is_err());
+	vk.assert_no_validation_errors();
+}
+
+#[test]
+fn compaction() {
+	let vk = vk_or_skip!();
+	let compact = Compact::new(&vk);
+	for &len in LENGTHS {
+		let data = random(len, -1000, 1000);
+		let keep: Vec<bool> = data.iter().map(|&v| v % 3 == 0).collect();
+		let want: Vec<i32> = data.iter().copied().filter(|v| v % 3 == 0).collect();
+		assert_eq!(compact.apply(&vk, &data, &keep), want, "len {}", len);
+	}
+
+	let floats = [1.5f32, -2.0, 3.25];
+	assert_eq!(compact.apply(&vk, &floats, &[true, false, true]), vec![1.5, 3.25]);
+	assert_eq!(compact.apply(&vk, &floats, &[false; 3]), Vec::<f32>::new());
+
+	// any nonzero flag keeps its element, without skipping output positions
+	let storage = BufferUsage {
+		storage_buffer: true,
+		..BufferUsage::none()
+	};
+	let (input, uploaded) = vk.upload(&[10u32, 20, 30, 40, 50], storage);
+	let (flags, flags_uploaded) = vk.upload(&[3u32, 0, 0xffff_ffff, 1, 0], storage);
+	uploaded.join(flags_uploaded).then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+	let mut builder = vk.auto_command_buffer_builder();
+	let (output, count) = compact.record(&vk, &mut builder, input, flags);
+	common::run(&vk, builder);
+	assert_eq!(vk.download(&count), vec![3]);
+	assert_eq!(vk.download(&output)[..3], [10, 30, 40]);
+	vk.assert_no_validation_errors();
+}
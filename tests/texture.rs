@@ -0,0 +1,90 @@
+#[macro_use]
+mod common;
+
+use vulkan_playground::*;
+use vulkano::image::ImageAccess;
+
+mod sample {
+	vulkano_shaders::shader! {
+		ty: "compute",
+		src: "
+#version 450
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1, local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+layout(set = 0, binding = 0) uniform texture2D tex;
+layout(set = 0, binding = 1) uniform sampler smp;
+layout(set = 0, binding = 2) buffer Texels { vec4 texels[]; };
+void main() {
+	uint level = gl_GlobalInvocationID.x;
+	texels[level] = textureLod(sampler2D(tex, smp), vec2(0.25, 0.25), float(level));
+}"
+	}
+}
+
+#[test]
+fn mipmaps() {
+	// 4x2 gray: 0 0 | 100 200
+	//           0 0 | 100 200
+	let levels = mipmap_chain(Format::R8Unorm, (4, 2), &[0, 0, 100, 200, 0, 0, 100, 200]);
+	assert_eq!(levels, vec![vec![0, 150], vec![75]]);
+	assert_eq!(mip_levels((4, 2)), 3);
+	assert_eq!(mip_levels((5, 5)), 3);
+	assert_eq!(mip_levels(Extent::cube(64)), 7);
+
+	// Pixels on the odd edge count towards both pixels of the next level.
+	let levels = mipmap_chain(Format::R32Sfloat, Extent::d1(5), bytemuck::cast_slice(&[1.0f32, 2.0, 3.0, 4.0, 5.0]));
+	assert_eq!(levels.len(), 2);
+	assert_eq!(bytemuck::cast_slice::<u8, f32>(&levels[0]), &[2.0, 4.0]);
+	assert_eq!(bytemuck::cast_slice::<u8, f32>(&levels[1]), &[3.0]);
+
+	// Layers are independent, and sRGB averages in linear space: mid gray is 188, not 128.
+	let layers = [0, 0, 0, 255, 255, 255, 255, 255, 9, 9, 9, 9, 9, 9, 9, 9];
+	let levels = mipmap_chain(Format::R8G8B8A8Srgb, Extent::d1_array(2, 2), &layers);
+	assert_eq!(levels, vec![vec![188, 188, 188, 255, 9, 9, 9, 9]]);
+}
+
+#[test]
+fn sampled_texture() {
+	let vk = vk_or_skip!();
+	let photo = image::RgbaImage::from_fn(4, 4, |x, y| image::Rgba([x as u8 * 60, y as u8 * 60, 255, 255]));
+	let texture = vk.texture(&DynamicImage::ImageRgba8(photo.clone()), true);
+	assert_eq!(texture.mipmap_levels(), 3);
+	assert_eq!(ImageAccess::format(&*texture), Format::R8G8B8A8Srgb);
+	let sampler = vk.sampler(SamplerOptions {
+		filter: Filter::Nearest,
+		mipmap_mode: MipmapMode::Nearest,
+		..Default::default()
+	});
+
+	let shader = sample::Shader::load(vk.device()).unwrap();
+	let kernel = Kernel::new(&vk, &shader.main_entry_point());
+	let texels = vk.cpu_buffer::<[f32; 4]>(3, BufferUsage::all());
+	let mut builder = vk.auto_command_buffer_builder();
+	kernel.record_dispatch(&mut builder, [3, 1, 1], (texture, sampler, texels.clone()), ());
+	common::run(&vk, builder);
+
+	// The texel at (0.25, 0.25) of each level: (1, 1) of 4x4, (0, 0) of 2x2, the single one of 1x1.
+	// Colors are sampled as linear values, from levels averaged in linear space; alpha is not sRGB encoded.
+	let levels = mipmap_chain(Format::R8G8B8A8Srgb, (4, 4), &photo);
+	let want = [&photo.get_pixel(1, 1).0[..], &levels[0][..4], &levels[1][..4]];
+	let encode = |v: f32| {
+		if v <= 0.003_130_8 {
+			v * 12.92
+		} else {
+			1.055 * v.powf(1.0 / 2.4) - 0.055
+		}
+	};
+	for (level, (got, want)) in texels.read().unwrap().iter().zip(&want).enumerate() {
+		for (c, (&got, &want)) in got.iter().zip(want.iter()).enumerate() {
+			let got = if c < 3 { encode(got) } else { got };
+			assert!(
+				(got * 255.0 - want as f32).abs() <= 1.0,
+				"level {} channel {}: {} != {}",
+				level,
+				c,
+				got * 255.0,
+				want
+			);
+		}
+	}
+	vk.assert_no_validation_errors();
+}
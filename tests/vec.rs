@@ -0,0 +1,118 @@
+use std::mem::{align_of, size_of};
+use vulkan_playground::*;
+use vulkano::image::Dimensions;
+
+#[test]
+fn vector_ops() {
+	let a = Vec3::new(1.0, 2.0, 3.0);
+	let b = Vec3::new(4.0, -5.0, 6.0);
+	assert_eq!(a + b, Vec3::new(5.0, -3.0, 9.0));
+	assert_eq!(a - b, Vec3::new(-3.0, 7.0, -3.0));
+	assert_eq!(a * b, Vec3::new(4.0, -10.0, 18.0));
+	assert_eq!(b / a, Vec3::new(4.0, -2.5, 2.0));
+	assert_eq!(2.0 * a, a * 2.0);
+	assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+	assert_eq!(a.dot(b), 12.0);
+	assert_eq!(a.cross(b), Vec3::new(27.0, 6.0, -13.0));
+	assert_eq!(a.cross(b).dot(a), 0.0);
+
+	let mut c = Vec4::splat(1.0);
+	c += Vec4::new(0.0, 1.0, 2.0, 3.0);
+	c *= 2.0;
+	c[3] = 0.5;
+	assert_eq!(c.to_array(), [2.0, 4.0, 6.0, 0.5]);
+	assert_eq!(Vec2::new(3.0, 4.0).length(), 5.0);
+	assert_eq!(Vec2::new(3.0, 4.0).normalize(), Vec2::new(0.6, 0.8));
+	assert_eq!(Vec2::ZERO.lerp(Vec2::new(2.0, 4.0), 0.25), Vec2::new(0.5, 1.0));
+
+	assert_eq!(IVec2::new(3, -4) * 2 - IVec2::splat(1), IVec2::new(5, -9));
+	assert_eq!(UVec3::new(7, 8, 9) / 2, UVec3::new(3, 4, 4));
+}
+
+#[test]
+fn matrix_ops() {
+	let m = Mat4::from([
+		[1.0, 2.0, 3.0, 4.0],
+		[5.0, 6.0, 7.0, 8.0],
+		[9.0, 10.0, 11.0, 12.0],
+		[13.0, 14.0, 15.0, 16.0],
+	]);
+	assert_eq!(m * Mat4::IDENTITY, m);
+	assert_eq!(m.transpose().transpose(), m);
+	assert_eq!(m.row(0), Vec4::new(1.0, 5.0, 9.0, 13.0));
+	assert_eq!(m * Vec4::new(1.0, 0.0, 0.0, 1.0), Vec4::new(14.0, 16.0, 18.0, 20.0));
+	assert_eq!((m * m).cols[0], m * m.cols[0]);
+
+	let p = Vec3::new(1.0, 2.0, 3.0).extend(1.0);
+	let moved = Mat4::translation(Vec3::new(10.0, 0.0, -1.0)) * Mat4::scale(Vec3::splat(2.0)) * p;
+	assert_eq!(moved, Vec4::new(12.0, 4.0, 5.0, 1.0));
+
+	let quarter = Mat3::rotation(Vec3::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2);
+	let rotated = quarter * Vec3::new(1.0, 0.0, 0.0);
+	assert!((rotated - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6, "{:?}", rotated);
+	let rotated = Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2) * Vec4::new(1.0, 0.0, 0.0, 1.0);
+	assert!((rotated - Vec4::new(0.0, 1.0, 0.0, 1.0)).length() < 1e-6, "{:?}", rotated);
+
+	let n = Mat3::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+	assert_eq!(n.transpose().col(0), Vec3::new(1.0, 4.0, 7.0));
+	assert_eq!(n * Mat3::IDENTITY, n);
+	assert_eq!(Mat3::from(Mat4::from(n)), n);
+	assert_eq!(
+		<[[f32; 4]; 3]>::from(n),
+		[[1.0, 2.0, 3.0, 0.0], [4.0, 5.0, 6.0, 0.0], [7.0, 8.0, 9.0, 0.0]]
+	);
+}
+
+// Sizes and alignments of the GLSL types in std430 blocks, with `Vec3` and `UVec3` tightly packed and `Vec3A` and `UVec3A` not.
+#[test]
+fn layout() {
+	assert_eq!((size_of::<Vec2>(), align_of::<Vec2>()), (8, 8));
+	assert_eq!((size_of::<Vec3>(), align_of::<Vec3>()), (12, 4));
+	assert_eq!((size_of::<Vec4>(), align_of::<Vec4>()), (16, 16));
+	assert_eq!((size_of::<IVec2>(), align_of::<IVec2>()), (8, 8));
+	assert_eq!((size_of::<UVec2>(), align_of::<UVec2>()), (8, 8));
+	assert_eq!((size_of::<UVec3>(), align_of::<UVec3>()), (12, 4));
+	assert_eq!((size_of::<Mat3>(), align_of::<Mat3>()), (48, 16));
+	assert_eq!((size_of::<Mat4>(), align_of::<Mat4>()), (64, 16));
+
+	assert_eq!((size_of::<Vec3A>(), align_of::<Vec3A>()), (16, 16));
+	assert_eq!((size_of::<UVec3A>(), align_of::<UVec3A>()), (16, 16));
+
+	// layout(std430) buffer Block { float scale; vec3 position; uvec3 cell; vec3 normals[2]; };
+	#[repr(C)]
+	struct Block {
+		scale: f32,
+		position: Vec3A,
+		cell: UVec3A,
+		normals: [Vec3A; 2],
+	}
+	let block = Block {
+		scale: 1.0,
+		position: Vec3::new(1.0, 2.0, 3.0).into(),
+		cell: UVec3A::new(4, 5, 6),
+		normals: [Vec3A::ZERO; 2],
+	};
+	let base = &block as *const Block as usize;
+	assert_eq!(&block.position as *const Vec3A as usize - base, 16);
+	assert_eq!(&block.cell as *const UVec3A as usize - base, 32);
+	assert_eq!(&block.normals[1] as *const Vec3A as usize - base, 64);
+	assert_eq!(block.scale, 1.0);
+	assert_eq!(Vec3::from(block.position), Vec3::new(1.0, 2.0, 3.0));
+	assert_eq!(block.cell.to_array(), [4, 5, 6]);
+	assert_eq!(bytemuck::cast::<Vec3A, [f32; 4]>(Vec3A::from([7.0, 8.0, 9.0])), [7.0, 8.0, 9.0, 0.0]);
+
+	let points = [Vec2::new(1.0, 2.0), Vec2::new(3.0, 4.0)];
+	assert_eq!(bytemuck::cast_slice::<Vec2, f32>(&points), &[1.0, 2.0, 3.0, 4.0]);
+}
+
+#[test]
+fn dimensions() {
+	let size = UVec2::from((640, 480));
+	assert_eq!(Dimensions::from(size).width_height(), [640, 480]);
+	assert_eq!(UVec2::from(Dimensions::from(size)), size);
+	assert_eq!(Vec2::from(size).to_array(), [640.0, 480.0]);
+
+	let volume = UVec3::new(4, 5, 6);
+	assert_eq!(Dimensions::from(volume).width_height_depth(), [4, 5, 6]);
+	assert_eq!(UVec3::from(Dimensions::Dim2d { width: 4, height: 5 }), UVec3::new(4, 5, 1));
+}